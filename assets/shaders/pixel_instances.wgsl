@@ -0,0 +1,125 @@
+#import bevy_sprite::mesh2d_view_bindings::view
+
+// number of points each decay curve is sampled at, DECAY_SAMPLES in decay.rs
+const DECAY_SAMPLES: u32 = 64u;
+
+// GpuPixelDecay in decay.rs, which does the same maths as the functions below
+struct PixelDecay {
+    // duration, floor, and 1 if channel_rates are used
+    params: vec4<f32>,
+    channel_rates: vec4<f32>,
+    // the curve sampled evenly over [0, 1], four to a vector
+    samples: array<vec4<f32>, 16>,
+};
+
+// game clock milliseconds since the clock epoch in x, clock_epoch in pixel_instances.rs
+@group(1) @binding(0) var<uniform> clock: vec4<f32>;
+@group(1) @binding(1) var<storage, read> decays: array<PixelDecay>;
+
+fn decay_sample(decay: u32, i: u32) -> f32 {
+    return decays[decay].samples[i / 4u][i % 4u];
+}
+
+fn fade(decay: u32, progress: f32) -> f32 {
+    let scaled = clamp(progress, 0.0, 1.0) * f32(DECAY_SAMPLES - 1u);
+    let i = min(u32(scaled), DECAY_SAMPLES - 2u);
+    let t = scaled - f32(i);
+
+    let a = decay_sample(decay, i);
+    let b = decay_sample(decay, i + 1u);
+
+    return a + (b - a) * t;
+}
+
+fn progress(decay: u32, elapsed: f32) -> f32 {
+    let duration = decays[decay].params.x;
+
+    if duration > 0.0 {
+        return elapsed / duration;
+    }
+
+    return 1.0;
+}
+
+fn brightness(decay: u32, elapsed: f32) -> f32 {
+    let min_brightness = decays[decay].params.y;
+
+    return min_brightness + (1.0 - min_brightness) * fade(decay, progress(decay, elapsed));
+}
+
+fn channel_factors(decay: u32, elapsed: f32) -> vec3<f32> {
+    if decays[decay].params.z == 0.0 {
+        return vec3<f32>(1.0);
+    }
+
+    let p = progress(decay, elapsed);
+    let rates = decays[decay].channel_rates;
+
+    return vec3<f32>(fade(decay, p * rates.x), fade(decay, p * rates.y), fade(decay, p * rates.z));
+}
+
+struct Instance {
+    // translation of the pixel's center, with its size in w
+    @location(0) translation_size: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) outline_color: vec4<f32>,
+    // lit time since the clock epoch, outline thickness, brightness or -1 to fade the pixel
+    // here, and decay index
+    @location(3) light: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // offset from the pixel's center
+    @location(0) local: vec2<f32>,
+    @location(1) half_size: f32,
+    @location(2) color: vec4<f32>,
+    @location(3) outline_color: vec4<f32>,
+};
+
+@vertex
+fn vertex(@builtin(vertex_index) vertex_index: u32, instance: Instance) -> VertexOutput {
+    var output: VertexOutput;
+
+    // corners of the quad shared by every pixel, drawn as a triangle strip
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u)) * 2.0 - 1.0;
+
+    let half_size = instance.translation_size.w * 0.5;
+    let outline_thickness = instance.light.y;
+
+    // grow the quad outwards to make room for the outline
+    let local = corner * (half_size + outline_thickness);
+    let world = vec4<f32>(instance.translation_size.xy + local, instance.translation_size.z, 1.0);
+
+    output.clip_position = view.clip_from_world * world;
+    output.local = local;
+    output.half_size = half_size;
+
+    var color = instance.color;
+
+    if instance.light.z >= 0.0 {
+        color.a *= instance.light.z;
+    } else {
+        let decay = u32(instance.light.w);
+        let elapsed = clock.x - instance.light.x;
+
+        color = vec4<f32>(
+            color.rgb * channel_factors(decay, elapsed),
+            color.a * brightness(decay, elapsed),
+        );
+    }
+
+    output.color = color;
+    output.outline_color = instance.outline_color;
+
+    return output;
+}
+
+@fragment
+fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+    if any(abs(input.local) > vec2<f32>(input.half_size)) {
+        return input.outline_color;
+    }
+
+    return input.color;
+}
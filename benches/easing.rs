@@ -0,0 +1,70 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use scanlined_bevy::easings::{
+    baked::{BakeSettings, Interpolation},
+    expr::BellShape,
+    CombinedEasing,
+};
+
+/// one periodic bell per user pixel, spread evenly over the scan
+fn user_pixel_easing(user_pixels: usize) -> CombinedEasing {
+    let mut easing = CombinedEasing::new(0.0);
+
+    for i in 0..user_pixels {
+        easing.extend(
+            BellShape::default()
+                .at(i as f64 / user_pixels as f64)
+                .with_periodic(true),
+        );
+    }
+
+    easing
+}
+
+fn evaluate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluate");
+
+    for user_pixels in [1, 10, 100] {
+        let exact = user_pixel_easing(user_pixels);
+
+        for (name, interpolation) in [
+            ("linear", Interpolation::Linear),
+            ("cubic", Interpolation::Cubic),
+        ] {
+            let baked = exact.clone().with_baking(Some(BakeSettings {
+                interpolation,
+                ..Default::default()
+            }));
+
+            group.bench_with_input(BenchmarkId::new(name, user_pixels), &baked, |b, easing| {
+                b.iter(|| easing.evaluate(black_box(0.37)))
+            });
+        }
+
+        group.bench_with_input(
+            BenchmarkId::new("exact", user_pixels),
+            &exact,
+            |b, easing| b.iter(|| easing.evaluate(black_box(0.37))),
+        );
+    }
+
+    group.finish();
+}
+
+fn bake(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bake");
+
+    for user_pixels in [1, 10, 100] {
+        let exact = user_pixel_easing(user_pixels);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(user_pixels),
+            &exact,
+            |b, easing| b.iter(|| easing.clone().with_baking(Some(BakeSettings::default()))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, evaluate, bake);
+criterion_main!(benches);
@@ -0,0 +1,97 @@
+use bevy::{
+    ecs::{entity::Entity, query::Changed, world::World},
+    utils::{HashMap, HashSet},
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use scanlined_bevy::{
+    grid::position::GridPosition,
+    pixels::{components::Pixel, index::PixelGrid},
+};
+
+const SIZES: [i32; 3] = [10, 100, 1000];
+
+fn spawn_grid(size: i32) -> World {
+    let mut world = World::new();
+    world.init_resource::<PixelGrid>();
+
+    world.spawn_batch(GridPosition::all(size, size).map(Pixel::new));
+
+    world
+}
+
+/// The same lookups `update_pixel_lit_time` makes, one cell after another.
+///
+/// `bevy_mod_index` was dropped along with the index it provided, so it can't be benchmarked
+/// here. `mod_index` stands in for it instead, doing what its `HashmapStorage` did on every use:
+/// scanning for `Pixel`s changed since the last refresh (with `IndexRefreshPolicy::WhenUsed`),
+/// then looking each cell up in a `HashMap<GridPosition, HashSet<Entity>>`.
+fn lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lookup");
+
+    for size in SIZES {
+        let mut world = spawn_grid(size);
+        world.clear_trackers();
+
+        let mut changed = world.query_filtered::<(Entity, &Pixel), Changed<Pixel>>();
+        let grid = world.resource::<PixelGrid>();
+
+        let mut storage = HashMap::<GridPosition, HashSet<Entity>>::default();
+        for (pos, entity) in grid.iter() {
+            storage.entry(pos).or_default().insert(entity);
+        }
+
+        let cells: Vec<_> = GridPosition::all(size, size).step_by(7).collect();
+
+        group.bench_with_input(BenchmarkId::new("pixel_grid", size), &cells, |b, cells| {
+            b.iter(|| {
+                for pos in cells {
+                    black_box(grid.get(black_box(pos)));
+                }
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("mod_index", size), &cells, |b, cells| {
+            b.iter(|| {
+                for changed in changed.iter(&world) {
+                    black_box(changed);
+                }
+
+                for pos in cells {
+                    black_box(
+                        storage
+                            .get(black_box(pos))
+                            .and_then(|entities| entities.iter().next()),
+                    );
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// spawning and despawning a whole grid, which runs the `Pixel` hooks once per cell
+fn rebuild(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rebuild");
+    group.sample_size(10);
+
+    for size in SIZES {
+        group.bench_function(BenchmarkId::from_parameter(size), |b| {
+            b.iter(|| {
+                let mut world = spawn_grid(size);
+                let entities: Vec<_> = world.resource::<PixelGrid>().iter().collect();
+
+                for (_, entity) in entities {
+                    world.despawn(entity);
+                }
+
+                world
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, lookup, rebuild);
+criterion_main!(benches);
@@ -8,27 +8,30 @@ use bevy::{
         system::{Res, ResMut, System},
         world::World,
     },
-    input::{common_conditions::input_toggle_active, keyboard::KeyCode},
     time::Time,
 };
-use bevy_egui::{EguiContext, EguiPlugin};
+use bevy_egui::{EguiContext, EguiPlugin, EguiPreUpdateSet};
 use bevy_inspector_egui::{
     bevy_inspector::{self, hierarchy::SelectedEntities},
     DefaultInspectorConfigPlugin,
 };
-use bevy_mod_index::storage::HashmapStorage;
 use bevy_window::PrimaryWindow;
 use egui::TextEdit;
 use egui_plot::{Line, Plot, PlotPoints, Points};
 
-use crate::pixels::components::{AllPixels, Pixel};
+use crate::grid::{field::InfluenceField, position::GridPosition};
+use crate::input::actions::{action_toggle_active, Action};
+use crate::input::picking::PickingBlocked;
+use crate::pixels::components::Pixel;
+use crate::pixels::systems::scan_easing_at;
+use crate::pixels::timeline::ScanTimeline;
 use crate::pixels::PIXEL_WAIT_TIME;
-use crate::scenes::story::{CombinedBellEasing, PixelStates};
-use crate::scenes::SceneState;
+use crate::scenes::clock::{set_clock_speed, StepClock, MAX_CLOCK_SPEED, MIN_CLOCK_SPEED};
+use crate::scenes::cursor::ScanCursor;
+use crate::scenes::story::CombinedBellEasing;
+use crate::scenes::{PauseState, SceneState};
 
 pub fn plugin(app: &mut App) {
-    app.register_type::<HashmapStorage<AllPixels>>();
-
     app.add_plugins((
         LogDiagnosticsPlugin::default(),
         FrameTimeDiagnosticsPlugin,
@@ -36,15 +39,48 @@ pub fn plugin(app: &mut App) {
         DefaultInspectorConfigPlugin,
     ));
 
+    app.add_systems(
+        PreUpdate,
+        block_picking_under_inspector.after(EguiPreUpdateSet::BeginPass),
+    );
     app.add_systems(
         Update,
-        inspector_ui.run_if(input_toggle_active(false, KeyCode::Escape)),
+        inspector_ui.run_if(action_toggle_active(false, Action::ToggleInspector)),
     );
 }
 
+/// Stops clicks on the inspector from reaching the pixels underneath it.
+fn block_picking_under_inspector(
+    mut commands: Commands,
+    mut egui_context: Query<&mut EguiContext, With<PrimaryWindow>>,
+    blocked: Option<Res<PickingBlocked>>,
+) {
+    let Ok(mut egui_context) = egui_context.get_single_mut() else {
+        return;
+    };
+
+    let ctx = egui_context.get_mut();
+    let over_inspector = ctx.is_pointer_over_area() || ctx.wants_pointer_input();
+
+    match (over_inspector, blocked.is_some()) {
+        (true, false) => commands.insert_resource(PickingBlocked),
+        (false, true) => commands.remove_resource::<PickingBlocked>(),
+        _ => {}
+    }
+}
+
 fn game_scene_panel(world: &mut World, egui_context: &mut EguiContext) {
-    let bell_curve_easing = world.resource::<CombinedBellEasing>();
-    let game_state = world.resource::<PixelStates>();
+    let mut cursors = world
+        .query::<(
+            &ScanCursor,
+            &CombinedBellEasing,
+            &InfluenceField,
+            &ScanTimeline,
+        )>()
+        .iter(world)
+        .collect::<Vec<_>>();
+
+    cursors.sort_by_key(|(cursor, ..)| cursor.index);
 
     egui::SidePanel::right("extras_inspector")
         .default_width(250.0)
@@ -52,41 +88,14 @@ fn game_scene_panel(world: &mut World, egui_context: &mut EguiContext) {
             ui.heading("Extras");
 
             egui::ScrollArea::both().show(ui, |ui| {
-                {
-                    let x = game_state.next_lit_pixel.normalised();
-
-                    let current_easing_val = bell_curve_easing.evaluate((x, 0.2, 1.0));
-
-                    {
-                        ui.label("Pixel Easing Curve");
-
-                        let sin: PlotPoints = (0..100)
-                            .map(|i| {
-                                let x = i as f64 / 100.0;
-
-                                [x, bell_curve_easing.evaluate((x, 0.2, 1.0))]
-                            })
-                            .collect();
-
-                        let current_position = Points::new([
-                            game_state.next_lit_pixel.normalised(),
-                            current_easing_val,
-                        ])
-                        .radius(6.0);
-
-                        let line = Line::new(sin);
-                        Plot::new("easing_plot")
-                            .view_aspect(2.0)
-                            .show(ui, |plot_ui| {
-                                plot_ui.line(line);
-                                plot_ui.points(current_position);
+                for (cursor, bell_curve_easing, field, timeline) in cursors {
+                    ui.push_id(cursor.index, |ui| {
+                        egui::CollapsingHeader::new(format!("Cursor {}", cursor.index))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                cursor_panel(ui, cursor, bell_curve_easing, field, timeline);
                             });
-                    }
-                    {
-                        ui.label("Time between pixels (ms)");
-                        let mut val = format!("{:.2}", PIXEL_WAIT_TIME * current_easing_val);
-                        ui.text_edit_singleline(&mut val);
-                    }
+                    });
                 }
 
                 ui.allocate_space(ui.available_size());
@@ -94,6 +103,126 @@ fn game_scene_panel(world: &mut World, egui_context: &mut EguiContext) {
         });
 }
 
+fn clock_window(world: &mut World, egui_context: &mut EguiContext) {
+    let was_paused = *world.resource::<State<PauseState>>().get() == PauseState::Paused;
+    let old_speed = world.resource::<Time<Virtual>>().relative_speed_f64();
+
+    let mut paused = was_paused;
+    let mut speed = old_speed;
+    let mut step = false;
+
+    egui::Window::new("Clock").show(egui_context.get_mut(), |ui| {
+        ui.checkbox(&mut paused, "Paused");
+        ui.add(
+            egui::Slider::new(&mut speed, MIN_CLOCK_SPEED..=MAX_CLOCK_SPEED)
+                .logarithmic(true)
+                .text("speed"),
+        );
+        ui.add_enabled_ui(was_paused, |ui| {
+            step = ui.button("Step").clicked();
+        });
+    });
+
+    // only apply what was changed here, so the keyboard controls aren't overridden
+    if paused != was_paused {
+        world
+            .resource_mut::<NextState<PauseState>>()
+            .set(if paused {
+                PauseState::Paused
+            } else {
+                PauseState::Running
+            });
+    }
+
+    if speed != old_speed {
+        set_clock_speed(&mut world.resource_mut::<Time<Virtual>>(), speed);
+    }
+
+    if step {
+        world.send_event(StepClock);
+    }
+}
+
+fn cursor_panel(
+    ui: &mut egui::Ui,
+    cursor: &ScanCursor,
+    bell_curve_easing: &CombinedBellEasing,
+    field: &InfluenceField,
+    timeline: &ScanTimeline,
+) {
+    let x = cursor.progress();
+
+    let current_easing_val =
+        scan_easing_at(cursor, bell_curve_easing, field, &cursor.next_lit_pixel);
+
+    {
+        ui.label("Pixel Easing Curve");
+
+        let scan_len = cursor.order.len();
+        let samples = scan_len.min(100);
+
+        // sampled per cell in scan order, so the 2-D influence field shows up too
+        let sin: PlotPoints = (0..samples)
+            .map(|i| {
+                let step = i * scan_len / samples;
+                let pos = GridPosition {
+                    packed: cursor.order.packed_at(step),
+                    ..cursor.next_lit_pixel
+                };
+
+                [
+                    step as f64 / scan_len as f64,
+                    scan_easing_at(cursor, bell_curve_easing, field, &pos),
+                ]
+            })
+            .collect();
+
+        let current_position = Points::new([x, current_easing_val]).radius(6.0);
+
+        let line = Line::new(sin);
+        Plot::new("easing_plot")
+            .view_aspect(2.0)
+            .show(ui, |plot_ui| {
+                plot_ui.line(line);
+                plot_ui.points(current_position);
+            });
+    }
+    {
+        egui::CollapsingHeader::new("Easing Expression").show(ui, |ui| {
+            ui.label(format!("base: {}", bell_curve_easing.base()));
+            ui.label(format!(
+                "combined with: {:?}",
+                bell_curve_easing.combinator()
+            ));
+
+            for (i, term) in bell_curve_easing.terms().iter().enumerate() {
+                ui.label(format!("{i}: {term}"));
+            }
+
+            if let Some(baked) = bell_curve_easing.baked() {
+                let settings = baked.settings();
+
+                ui.label(format!(
+                    "baked: {} samples, {:?}, max error {:.2e}",
+                    settings.resolution,
+                    settings.interpolation,
+                    baked.max_error()
+                ));
+            }
+        });
+    }
+    {
+        ui.label("Time between pixels (ms)");
+        let mut val = format!("{:.2}", PIXEL_WAIT_TIME * current_easing_val / cursor.speed);
+        ui.text_edit_singleline(&mut val);
+    }
+    {
+        ui.label("Sweep duration (ms)");
+        let mut val = format!("{:.2}", timeline.sweep_duration());
+        ui.text_edit_singleline(&mut val);
+    }
+}
+
 fn inspector_ui(world: &mut World) {
     let Ok(egui_context) = world
         .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
@@ -142,6 +271,9 @@ fn inspector_ui(world: &mut World) {
 
     match current_scene.get() {
         SceneState::MainMenu => (),
-        SceneState::Game => game_scene_panel(world, &mut egui_context),
+        SceneState::Game => {
+            game_scene_panel(world, &mut egui_context);
+            clock_window(world, &mut egui_context);
+        }
     }
 }
@@ -0,0 +1,199 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use super::{
+    baked::BakeSettings,
+    expr::{BellShape, EasingExpr},
+    Combinator, CombinedEasing,
+};
+
+/// An easing described in a `.easing.ron` file.
+///
+/// ```ron
+/// (
+///     base: Constant(0.05),
+///     combinator: Max,
+///     terms: [Bell((center: 0.5, width: 0.1, sharpness: 2.0))],
+///     user_pixel_bell: (width: 0.2, sharpness: 1.0),
+///     bake: Some((resolution: 1024, interpolation: Linear)),
+/// )
+/// ```
+#[derive(Asset, TypePath, Deserialize, Clone, Debug, PartialEq)]
+pub struct EasingAsset {
+    pub base: EasingExpr,
+    #[serde(default)]
+    pub combinator: Combinator,
+    #[serde(default)]
+    pub terms: Vec<EasingExpr>,
+    /// bell curve placed around user pixels that don't override it
+    #[serde(default)]
+    pub user_pixel_bell: BellShape,
+    /// sample the finished easing into a lookup table instead of evaluating every term each frame
+    #[serde(default)]
+    pub bake: Option<BakeSettings>,
+}
+
+impl EasingAsset {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EasingAssetLoaderError> {
+        Ok(ron::de::from_bytes(bytes)?)
+    }
+
+    pub fn to_combined(&self) -> CombinedEasing {
+        let mut easing = CombinedEasing::new(self.base.clone()).with_combinator(self.combinator);
+
+        for term in &self.terms {
+            easing.extend(term.clone());
+        }
+
+        easing
+    }
+}
+
+#[derive(Debug)]
+pub enum EasingAssetLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for EasingAssetLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read easing asset: {err}"),
+            Self::Ron(err) => write!(f, "could not parse easing asset: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for EasingAssetLoaderError {}
+
+impl From<std::io::Error> for EasingAssetLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for EasingAssetLoaderError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Ron(err)
+    }
+}
+
+#[derive(Default)]
+pub struct EasingAssetLoader;
+
+impl AssetLoader for EasingAssetLoader {
+    type Asset = EasingAsset;
+    type Settings = ();
+    type Error = EasingAssetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        EasingAsset::from_bytes(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["easing.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easings::baked::Interpolation;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    #[test]
+    fn parses_shipped_scan_easing() {
+        let asset = EasingAsset::from_bytes(include_bytes!("../../assets/easings/scan.easing.ron"))
+            .unwrap();
+
+        assert_eq!(asset.base, EasingExpr::Constant(0.0));
+        assert_eq!(asset.combinator, Combinator::Max);
+        assert!(asset.terms.is_empty());
+        assert_eq!(
+            asset.user_pixel_bell,
+            BellShape {
+                width: 0.2,
+                sharpness: 1.0
+            }
+        );
+        assert_eq!(
+            asset.bake,
+            Some(BakeSettings {
+                resolution: 1024,
+                interpolation: Interpolation::Linear
+            })
+        );
+    }
+
+    #[test]
+    fn optional_fields_default() {
+        let asset = EasingAsset::from_bytes(b"(base: Linear)").unwrap();
+
+        assert_eq!(asset.base, EasingExpr::Linear);
+        assert_eq!(asset.combinator, Combinator::default());
+        assert!(asset.terms.is_empty());
+        assert_eq!(asset.user_pixel_bell, BellShape::default());
+        assert_eq!(asset.bake, None);
+    }
+
+    #[test]
+    fn malformed_file_is_a_loader_error() {
+        for bytes in [
+            &b"(base: Constant(0.0)"[..],
+            b"(combinator: Max)",
+            b"(base: Sideways)",
+            b"",
+        ] {
+            let err = EasingAsset::from_bytes(bytes).unwrap_err();
+
+            assert!(
+                matches!(err, EasingAssetLoaderError::Ron(_)),
+                "{}: {err}",
+                String::from_utf8_lossy(bytes)
+            );
+            assert!(err.to_string().starts_with("could not parse easing asset"));
+        }
+    }
+
+    #[test]
+    fn to_combined_folds_terms_with_the_combinator() {
+        let asset = EasingAsset::from_bytes(
+            b"(
+                base: Constant(0.05),
+                combinator: Max,
+                terms: [Bell((center: 0.5, width: 0.1, sharpness: 2.0))],
+            )",
+        )
+        .unwrap();
+        let easing = asset.to_combined();
+
+        assert_eq!(easing.base(), &asset.base);
+        assert_eq!(easing.terms(), asset.terms.as_slice());
+        assert_eq!(easing.combinator(), Combinator::Max);
+        assert_close(easing.evaluate_exact(0.5), 1.0);
+        assert_close(easing.evaluate_exact(0.0), 0.05);
+
+        let asset = EasingAsset::from_bytes(
+            b"(base: Constant(0.25), combinator: Add, terms: [Linear, Constant(0.5)])",
+        )
+        .unwrap();
+
+        assert_close(asset.to_combined().evaluate_exact(0.5), 1.25);
+    }
+}
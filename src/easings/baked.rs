@@ -0,0 +1,246 @@
+use serde::Deserialize;
+
+/// How a [`BakedEasing`] fills in the values between its samples.
+#[derive(Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Catmull-Rom spline through the samples
+    Cubic,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BakeSettings {
+    /// number of intervals the [0, 1] domain is split into
+    pub resolution: usize,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+impl Default for BakeSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 1024,
+            interpolation: Interpolation::default(),
+        }
+    }
+}
+
+/// An easing sampled into a lookup table over [0, 1], so evaluating it costs the same no matter
+/// how many terms went into it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BakedEasing {
+    samples: Vec<f64>,
+    settings: BakeSettings,
+    max_error: f64,
+}
+
+impl BakedEasing {
+    /// evenly spaced points the error is measured at across every interval
+    const ERROR_PROBES: usize = 16;
+
+    pub fn bake(easing: impl Fn(f64) -> f64, settings: BakeSettings) -> Self {
+        let resolution = settings.resolution.max(1);
+
+        let samples = (0..=resolution)
+            .map(|i| easing(i as f64 / resolution as f64))
+            .collect();
+
+        let mut baked = Self {
+            samples,
+            settings: BakeSettings {
+                resolution,
+                ..settings
+            },
+            max_error: 0.0,
+        };
+
+        baked.max_error = baked.error_bound(easing);
+
+        baked
+    }
+
+    pub fn settings(&self) -> BakeSettings {
+        self.settings
+    }
+
+    /// Upper bound on the difference from the original easing anywhere in [0, 1], for easings
+    /// that are smooth apart from the odd kink.
+    pub fn max_error(&self) -> f64 {
+        self.max_error
+    }
+
+    /// Measures the error at [`Self::ERROR_PROBES`] points across every interval, then covers the
+    /// gap between each pair of neighbouring probes by allowing the error to change there up to
+    /// twice as fast as it does between the probes around them. That holds for anything smooth,
+    /// and for kinks (like the bounces of `BounceOut`) that fall between two probes.
+    fn error_bound(&self, easing: impl Fn(f64) -> f64) -> f64 {
+        let probes = self.settings.resolution * Self::ERROR_PROBES;
+        let spacing = 1.0 / probes as f64;
+
+        let errors = (0..=probes)
+            .map(|i| i as f64 * spacing)
+            .map(|x| (self.evaluate(x) - easing(x)).abs())
+            .collect::<Vec<_>>();
+
+        let slopes = errors
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs() / spacing)
+            .collect::<Vec<_>>();
+
+        (0..probes)
+            .map(|i| {
+                let steepest = slopes[i.saturating_sub(1)..(i + 2).min(probes)]
+                    .iter()
+                    .fold(0.0, |a: f64, &b| a.max(b));
+
+                // the most anything changing no faster than `2 * steepest` rises between the two
+                (errors[i] + errors[i + 1]) / 2.0 + steepest * spacing
+            })
+            .fold(0.0, f64::max)
+    }
+
+    fn sample(&self, i: isize) -> f64 {
+        self.samples[i.clamp(0, self.samples.len() as isize - 1) as usize]
+    }
+
+    pub fn evaluate(&self, x: f64) -> f64 {
+        let scaled = x.clamp(0.0, 1.0) * self.settings.resolution as f64;
+
+        let i = (scaled.floor() as isize).min(self.settings.resolution as isize - 1);
+        let t = scaled - i as f64;
+
+        match self.settings.interpolation {
+            Interpolation::Linear => {
+                let (a, b) = (self.sample(i), self.sample(i + 1));
+
+                a + (b - a) * t
+            }
+            Interpolation::Cubic => {
+                let (p0, p1, p2, p3) = (
+                    self.sample(i - 1),
+                    self.sample(i),
+                    self.sample(i + 1),
+                    self.sample(i + 2),
+                );
+
+                0.5 * ((2.0 * p1)
+                    + (-p0 + p2) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+                    + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * t * t * t)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::easings::{expr::BellShape, standard::StandardEasing, Easing};
+
+    use super::*;
+
+    fn settings(resolution: usize, interpolation: Interpolation) -> BakeSettings {
+        BakeSettings {
+            resolution,
+            interpolation,
+        }
+    }
+
+    /// largest difference from `easing` over a fixed grid, much finer than the table's probes
+    fn measured_error(baked: &BakedEasing, easing: impl Fn(f64) -> f64) -> f64 {
+        (0..=100_000)
+            .map(|i| i as f64 / 100_000.0)
+            .map(|x| (baked.evaluate(x) - easing(x)).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn matches_source_at_samples() {
+        let easing = |x| StandardEasing::SineInOut.evaluate(x);
+
+        for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+            let baked = BakedEasing::bake(easing, settings(16, interpolation));
+
+            for i in 0..=16 {
+                let x = i as f64 / 16.0;
+
+                assert!((baked.evaluate(x) - easing(x)).abs() < 1e-12, "{x}");
+            }
+        }
+    }
+
+    #[test]
+    fn follows_source_between_samples() {
+        let bell = BellShape {
+            width: 0.1,
+            sharpness: 2.0,
+        }
+        .at(0.4);
+        let easing = |x| bell.evaluate(x);
+
+        let linear = BakedEasing::bake(easing, settings(256, Interpolation::Linear));
+        let cubic = BakedEasing::bake(easing, settings(256, Interpolation::Cubic));
+
+        let linear_error = measured_error(&linear, easing);
+        let cubic_error = measured_error(&cubic, easing);
+
+        assert!(linear_error < 1e-3, "{linear_error}");
+        assert!(cubic_error < 1e-3, "{cubic_error}");
+    }
+
+    #[test]
+    fn cubic_is_closer_on_smooth_easings() {
+        let easing = |x| StandardEasing::SineInOut.evaluate(x);
+
+        let linear = BakedEasing::bake(easing, settings(32, Interpolation::Linear));
+        let cubic = BakedEasing::bake(easing, settings(32, Interpolation::Cubic));
+
+        assert!(measured_error(&cubic, easing) < measured_error(&linear, easing));
+    }
+
+    #[test]
+    fn linear_easings_bake_exactly() {
+        let baked = BakedEasing::bake(|x| x, settings(8, Interpolation::Linear));
+
+        assert!(baked.max_error() < 1e-12);
+        assert!(measured_error(&baked, |x| x) < 1e-12);
+    }
+
+    #[test]
+    fn max_error_bounds_measured() {
+        let bell = BellShape {
+            width: 0.05,
+            sharpness: 2.0,
+        }
+        .at(0.3);
+
+        let easings: [&dyn Fn(f64) -> f64; 3] = [
+            &|x| StandardEasing::BounceOut.evaluate(x),
+            &|x| StandardEasing::ElasticOut.evaluate(x),
+            &|x| bell.evaluate(x),
+        ];
+
+        for easing in easings {
+            for interpolation in [Interpolation::Linear, Interpolation::Cubic] {
+                for resolution in [16, 64, 250] {
+                    let baked = BakedEasing::bake(easing, settings(resolution, interpolation));
+                    let measured = measured_error(&baked, easing);
+
+                    assert!(
+                        baked.max_error() >= measured && baked.max_error() <= measured * 1.5,
+                        "{interpolation:?} x{resolution}: {} vs {measured}",
+                        baked.max_error()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_resolution_bakes_one_interval() {
+        let baked = BakedEasing::bake(|x| x * x, settings(0, Interpolation::Linear));
+
+        assert_eq!(baked.settings().resolution, 1);
+        assert_eq!(baked.evaluate(0.5), 0.5);
+    }
+}
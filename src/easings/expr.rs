@@ -0,0 +1,374 @@
+use std::fmt;
+
+use bevy::reflect::Reflect;
+use serde::Deserialize;
+
+use super::{
+    bell_curve, circular_distance, standard::StandardEasing, Easing, DEFAULT_BELL_SHARPNESS,
+    DEFAULT_BELL_WIDTH,
+};
+
+/// Width and sharpness of a bell curve, without a center.
+#[derive(Reflect, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct BellShape {
+    pub width: f64,
+    pub sharpness: f64,
+}
+
+impl Default for BellShape {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_BELL_WIDTH,
+            sharpness: DEFAULT_BELL_SHARPNESS,
+        }
+    }
+}
+
+impl BellShape {
+    pub fn at(self, center: f64) -> BellCurve {
+        BellCurve {
+            center,
+            width: self.width,
+            sharpness: self.sharpness,
+            periodic: false,
+        }
+    }
+}
+
+/// A bell curve peaking at 1.0 at `center`.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq)]
+pub struct BellCurve {
+    pub center: f64,
+    pub width: f64,
+    pub sharpness: f64,
+    /// Measure the distance to `center` around the [0, 1) circle, so a curve near one end of the
+    /// domain carries on from the other end instead of stopping abruptly.
+    #[serde(default)]
+    pub periodic: bool,
+}
+
+impl BellCurve {
+    pub fn with_periodic(mut self, periodic: bool) -> Self {
+        self.periodic = periodic;
+        self
+    }
+}
+
+impl Easing for BellCurve {
+    fn evaluate(&self, x: f64) -> f64 {
+        if self.periodic {
+            let distance = circular_distance(x, self.center);
+
+            bell_curve((distance, 0.0, self.width, self.sharpness))
+        } else {
+            bell_curve((x, self.center, self.width, self.sharpness))
+        }
+    }
+}
+
+impl From<BellCurve> for EasingExpr {
+    fn from(bell: BellCurve) -> Self {
+        Self::Bell(bell)
+    }
+}
+
+/// An easing built out of other easings, kept as a tree so it can be inspected and edited.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum EasingExpr {
+    Constant(f64),
+    /// `f(x) = x`
+    Linear,
+    Bell(BellCurve),
+    Standard(StandardEasing),
+    Max(Vec<EasingExpr>),
+    Min(Vec<EasingExpr>),
+    Add(Vec<EasingExpr>),
+    Multiply(Vec<EasingExpr>),
+    /// weighted average of the terms
+    Blend(Vec<(f64, EasingExpr)>),
+    Clamp {
+        inner: Box<EasingExpr>,
+        min: f64,
+        max: f64,
+    },
+    /// Linearly maps the output of `inner` from the `from` range onto the `to` range. An empty
+    /// `from` range maps everything below it to `to.0` and the rest to `to.1`.
+    Remap {
+        inner: Box<EasingExpr>,
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    /// `1 - f(x)`
+    Invert(Box<EasingExpr>),
+    /// Segments ending at the given x, in ascending order. Each segment is evaluated with x
+    /// rescaled to [0, 1] over its own span. Segments with no span are skipped, and x
+    /// past the last segment evaluates the last segment that has a span at 1.
+    Piecewise(Vec<(f64, EasingExpr)>),
+}
+
+impl Default for EasingExpr {
+    fn default() -> Self {
+        Self::Constant(0.0)
+    }
+}
+
+impl From<f64> for EasingExpr {
+    fn from(value: f64) -> Self {
+        Self::Constant(value)
+    }
+}
+
+impl Easing for EasingExpr {
+    fn evaluate(&self, x: f64) -> f64 {
+        match self {
+            Self::Constant(value) => *value,
+            Self::Linear => x,
+            Self::Bell(bell) => bell.evaluate(x),
+            Self::Standard(easing) => easing.evaluate(x),
+            Self::Max(terms) => terms
+                .iter()
+                .map(|e| e.evaluate(x))
+                .fold(f64::NEG_INFINITY, f64::max),
+            Self::Min(terms) => terms
+                .iter()
+                .map(|e| e.evaluate(x))
+                .fold(f64::INFINITY, f64::min),
+            Self::Add(terms) => terms.iter().map(|e| e.evaluate(x)).sum(),
+            Self::Multiply(terms) => terms.iter().map(|e| e.evaluate(x)).product(),
+            Self::Blend(terms) => {
+                let total: f64 = terms.iter().map(|(w, _)| w).sum();
+
+                if total == 0.0 {
+                    return 0.0;
+                }
+
+                terms.iter().map(|(w, e)| w * e.evaluate(x)).sum::<f64>() / total
+            }
+            Self::Clamp { inner, min, max } => inner.evaluate(x).clamp(*min, *max),
+            Self::Remap { inner, from, to } => {
+                let value = inner.evaluate(x);
+                let span = from.1 - from.0;
+
+                // an empty `from` range is a step at its one value
+                let t = if span == 0.0 {
+                    if value < from.0 {
+                        0.0
+                    } else {
+                        1.0
+                    }
+                } else {
+                    (value - from.0) / span
+                };
+
+                to.0 + t * (to.1 - to.0)
+            }
+            Self::Invert(inner) => 1.0 - inner.evaluate(x),
+            Self::Piecewise(segments) => {
+                let mut start = 0.0;
+                let mut last = None;
+
+                for (end, easing) in segments {
+                    // segments that don't end past the previous one cover no x at all
+                    if *end <= start {
+                        continue;
+                    }
+
+                    if x < *end {
+                        return easing.evaluate((x - start) / (end - start));
+                    }
+
+                    start = *end;
+                    last = Some(easing);
+                }
+
+                last.map(|easing| easing.evaluate(1.0)).unwrap_or_default()
+            }
+        }
+    }
+}
+
+fn write_list<'a>(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    terms: impl IntoIterator<Item = &'a EasingExpr>,
+) -> fmt::Result {
+    write!(f, "{name}(")?;
+
+    for (i, term) in terms.into_iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+
+        write!(f, "{term}")?;
+    }
+
+    write!(f, ")")
+}
+
+impl fmt::Display for EasingExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Constant(value) => write!(f, "{value:.2}"),
+            Self::Linear => write!(f, "x"),
+            Self::Bell(bell) => write!(
+                f,
+                "{}({:.2}, w={:.2}, s={:.2})",
+                if bell.periodic {
+                    "periodic_bell"
+                } else {
+                    "bell"
+                },
+                bell.center,
+                bell.width,
+                bell.sharpness
+            ),
+            Self::Standard(easing) => write!(f, "{easing}"),
+            Self::Max(terms) => write_list(f, "max", terms),
+            Self::Min(terms) => write_list(f, "min", terms),
+            Self::Add(terms) => write_list(f, "add", terms),
+            Self::Multiply(terms) => write_list(f, "mul", terms),
+            Self::Blend(terms) => {
+                write!(f, "blend(")?;
+
+                for (i, (weight, term)) in terms.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{weight:.2} * {term}")?;
+                }
+
+                write!(f, ")")
+            }
+            Self::Clamp { inner, min, max } => write!(f, "clamp({inner}, {min:.2}, {max:.2})"),
+            Self::Remap { inner, from, to } => write!(
+                f,
+                "remap({inner}, {:.2}..{:.2} -> {:.2}..{:.2})",
+                from.0, from.1, to.0, to.1
+            ),
+            Self::Invert(inner) => write!(f, "1 - {inner}"),
+            Self::Piecewise(segments) => {
+                write!(f, "piecewise(")?;
+
+                for (i, (end, term)) in segments.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "..{end:.2}: {term}")?;
+                }
+
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    #[test]
+    fn combinators() {
+        let expr = EasingExpr::Linear;
+
+        assert_close(expr.clone().max(0.5).evaluate(0.25), 0.5);
+        assert_close(expr.clone().min(0.5).evaluate(0.25), 0.25);
+        assert_close(expr.clone().add(0.5).evaluate(0.25), 0.75);
+        assert_close(expr.clone().multiply(0.5).evaluate(0.25), 0.125);
+        assert_close(expr.clone().blend(1.0, 0.25).evaluate(0.5), 0.625);
+        assert_close(expr.clone().clamp(0.2, 0.4).evaluate(0.9), 0.4);
+        assert_close(expr.invert().evaluate(0.25), 0.75);
+    }
+
+    #[test]
+    fn blend_without_weight_is_zero() {
+        let expr = EasingExpr::Blend(vec![(0.0, EasingExpr::Constant(1.0))]);
+
+        assert_eq!(expr.evaluate(0.5), 0.0);
+    }
+
+    #[test]
+    fn remap() {
+        let expr = EasingExpr::Linear.remap((0.5, 1.0), (1.0, 3.0));
+
+        assert_close(expr.evaluate(0.5), 1.0);
+        assert_close(expr.evaluate(0.75), 2.0);
+        assert_close(expr.evaluate(0.25), 0.0);
+    }
+
+    #[test]
+    fn remap_empty_range_is_a_step() {
+        let expr = EasingExpr::Linear.remap((0.5, 0.5), (1.0, 3.0));
+
+        assert_eq!(expr.evaluate(0.25), 1.0);
+        assert_eq!(expr.evaluate(0.5), 3.0);
+        assert_eq!(expr.evaluate(0.75), 3.0);
+    }
+
+    #[test]
+    fn piecewise() {
+        let expr = EasingExpr::Linear.then(EasingExpr::Linear.invert(), 0.5);
+
+        assert_close(expr.evaluate(0.25), 0.5);
+        assert_close(expr.evaluate(0.5), 1.0);
+        assert_close(expr.evaluate(0.75), 0.5);
+        assert_close(expr.evaluate(1.0), 0.0);
+    }
+
+    #[test]
+    fn piecewise_skips_empty_segments() {
+        let expr = EasingExpr::Piecewise(vec![
+            (0.0, EasingExpr::Constant(5.0)),
+            (0.5, EasingExpr::Linear),
+            (0.5, EasingExpr::Constant(5.0)),
+            (1.0, EasingExpr::Linear),
+        ]);
+
+        for x in [-0.25, 0.0, 0.25, 0.5, 0.75, 1.0, 1.25] {
+            let value = expr.evaluate(x);
+
+            assert!(value.is_finite(), "{x}: {value}");
+            assert_ne!(value, 5.0, "{x}");
+        }
+
+        assert_close(expr.evaluate(0.25), 0.5);
+        assert_close(expr.evaluate(0.75), 0.5);
+    }
+
+    #[test]
+    fn piecewise_ignores_trailing_empty_segment() {
+        let expr = EasingExpr::Piecewise(vec![
+            (0.5, EasingExpr::Constant(2.0)),
+            (1.0, EasingExpr::Linear),
+            (1.0, EasingExpr::Constant(5.0)),
+        ]);
+
+        assert_close(expr.evaluate(1.0), 1.0);
+        assert_close(expr.evaluate(1.5), 1.0);
+
+        let empty = EasingExpr::Piecewise(vec![(0.0, EasingExpr::Constant(5.0))]);
+
+        assert_eq!(empty.evaluate(0.5), 0.0);
+    }
+
+    #[test]
+    fn bell_peaks_at_center() {
+        let bell = BellShape::default().at(0.3);
+
+        assert_eq!(bell.evaluate(0.3), 1.0);
+        assert!(bell.evaluate(0.2) < 1.0);
+        assert_close(bell.evaluate(0.2), bell.evaluate(0.4));
+    }
+
+    #[test]
+    fn periodic_bell_wraps_around() {
+        let bell = BellShape::default().at(0.05).with_periodic(true);
+
+        assert_close(bell.evaluate(0.95), bell.evaluate(0.15));
+    }
+}
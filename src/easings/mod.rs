@@ -0,0 +1,322 @@
+pub mod asset;
+pub mod baked;
+pub mod expr;
+pub mod standard;
+
+use asset::{EasingAsset, EasingAssetLoader};
+use baked::{BakeSettings, BakedEasing};
+use bevy::{app::App, asset::AssetApp, ecs::component::Component};
+use expr::EasingExpr;
+use serde::Deserialize;
+
+pub const DEFAULT_BELL_WIDTH: f64 = 0.2;
+pub const DEFAULT_BELL_SHARPNESS: f64 = 1.0;
+
+pub fn plugin(app: &mut App) {
+    app.init_asset::<EasingAsset>();
+    app.init_asset_loader::<EasingAssetLoader>();
+}
+
+/// Something that maps a normalised input onto an easing value, and can be combined with other
+/// easings into an [`EasingExpr`].
+pub trait Easing: Into<EasingExpr> {
+    fn evaluate(&self, x: f64) -> f64;
+
+    fn max(self, other: impl Into<EasingExpr>) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        match self.into() {
+            EasingExpr::Max(mut terms) => {
+                terms.push(other.into());
+                EasingExpr::Max(terms)
+            }
+            expr => EasingExpr::Max(vec![expr, other.into()]),
+        }
+    }
+
+    fn min(self, other: impl Into<EasingExpr>) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        match self.into() {
+            EasingExpr::Min(mut terms) => {
+                terms.push(other.into());
+                EasingExpr::Min(terms)
+            }
+            expr => EasingExpr::Min(vec![expr, other.into()]),
+        }
+    }
+
+    fn add(self, other: impl Into<EasingExpr>) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        match self.into() {
+            EasingExpr::Add(mut terms) => {
+                terms.push(other.into());
+                EasingExpr::Add(terms)
+            }
+            expr => EasingExpr::Add(vec![expr, other.into()]),
+        }
+    }
+
+    fn multiply(self, other: impl Into<EasingExpr>) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        match self.into() {
+            EasingExpr::Multiply(mut terms) => {
+                terms.push(other.into());
+                EasingExpr::Multiply(terms)
+            }
+            expr => EasingExpr::Multiply(vec![expr, other.into()]),
+        }
+    }
+
+    /// `(1 - t) * self + t * other`
+    fn blend(self, other: impl Into<EasingExpr>, t: f64) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        EasingExpr::Blend(vec![(1.0 - t, self.into()), (t, other.into())])
+    }
+
+    fn clamp(self, min: f64, max: f64) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        EasingExpr::Clamp {
+            inner: Box::new(self.into()),
+            min,
+            max,
+        }
+    }
+
+    fn remap(self, from: (f64, f64), to: (f64, f64)) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        EasingExpr::Remap {
+            inner: Box::new(self.into()),
+            from,
+            to,
+        }
+    }
+
+    fn invert(self) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        EasingExpr::Invert(Box::new(self.into()))
+    }
+
+    /// `self` up until `at`, then `other` for the rest of the domain
+    fn then(self, other: impl Into<EasingExpr>, at: f64) -> EasingExpr
+    where
+        Self: Sized,
+    {
+        match self.into() {
+            EasingExpr::Piecewise(mut segments) => {
+                if let Some(last) = segments.last_mut() {
+                    last.0 = at;
+                }
+
+                segments.push((1.0, other.into()));
+                EasingExpr::Piecewise(segments)
+            }
+            expr => EasingExpr::Piecewise(vec![(at, expr), (1.0, other.into())]),
+        }
+    }
+}
+
+/// How the terms of a [`CombinedEasing`] are folded together.
+#[derive(Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Combinator {
+    #[default]
+    Max,
+    Min,
+    Add,
+    Multiply,
+}
+
+impl Combinator {
+    pub fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            Self::Max => a.max(b),
+            Self::Min => a.min(b),
+            Self::Add => a + b,
+            Self::Multiply => a * b,
+        }
+    }
+}
+
+/// A base easing combined with a list of terms that can be listed, removed and replaced.
+///
+/// With baking enabled the easing is sampled into a [`BakedEasing`] whenever its terms change, so
+/// [`CombinedEasing::evaluate`] stays cheap no matter how many terms there are.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct CombinedEasing {
+    base: EasingExpr,
+    terms: Vec<EasingExpr>,
+    combinator: Combinator,
+    bake: Option<BakeSettings>,
+    baked: Option<BakedEasing>,
+}
+
+impl CombinedEasing {
+    pub fn new(base: impl Into<EasingExpr>) -> Self {
+        Self {
+            base: base.into(),
+            terms: Vec::new(),
+            combinator: Combinator::default(),
+            bake: None,
+            baked: None,
+        }
+    }
+
+    pub fn with_combinator(mut self, combinator: Combinator) -> Self {
+        self.combinator = combinator;
+        self.rebake();
+        self
+    }
+
+    pub fn with_baking(mut self, settings: Option<BakeSettings>) -> Self {
+        self.bake = settings;
+        self.rebake();
+        self
+    }
+
+    /// adds a term, returning its index
+    pub fn extend(&mut self, next: impl Into<EasingExpr>) -> usize {
+        self.terms.push(next.into());
+        self.rebake();
+        self.terms.len() - 1
+    }
+
+    pub fn base(&self) -> &EasingExpr {
+        &self.base
+    }
+
+    pub fn terms(&self) -> &[EasingExpr] {
+        &self.terms
+    }
+
+    pub fn combinator(&self) -> Combinator {
+        self.combinator
+    }
+
+    /// the lookup table `evaluate` reads from, if baking is enabled
+    pub fn baked(&self) -> Option<&BakedEasing> {
+        self.baked.as_ref()
+    }
+
+    pub fn remove(&mut self, index: usize) -> EasingExpr {
+        let term = self.terms.remove(index);
+        self.rebake();
+        term
+    }
+
+    pub fn replace(&mut self, index: usize, term: impl Into<EasingExpr>) -> EasingExpr {
+        let term = std::mem::replace(&mut self.terms[index], term.into());
+        self.rebake();
+        term
+    }
+
+    pub fn clear(&mut self) {
+        self.terms.clear();
+        self.rebake();
+    }
+
+    fn rebake(&mut self) {
+        self.baked = self
+            .bake
+            .map(|settings| BakedEasing::bake(|x| self.evaluate_exact(x), settings));
+    }
+
+    /// the whole easing as a single expression tree
+    pub fn expr(&self) -> EasingExpr {
+        let terms = std::iter::once(self.base.clone())
+            .chain(self.terms.iter().cloned())
+            .collect();
+
+        match self.combinator {
+            Combinator::Max => EasingExpr::Max(terms),
+            Combinator::Min => EasingExpr::Min(terms),
+            Combinator::Add => EasingExpr::Add(terms),
+            Combinator::Multiply => EasingExpr::Multiply(terms),
+        }
+    }
+
+    pub fn evaluate(&self, x: f64) -> f64 {
+        match &self.baked {
+            Some(baked) => baked.evaluate(x),
+            None => self.evaluate_exact(x),
+        }
+    }
+
+    /// evaluates the expression tree directly, skipping the baked lookup table
+    pub fn evaluate_exact(&self, x: f64) -> f64 {
+        self.terms.iter().fold(self.base.evaluate(x), |acc, term| {
+            self.combinator.apply(acc, term.evaluate(x))
+        })
+    }
+}
+
+/// distance between `a` and `b` on the [0, 1) circle
+#[inline]
+pub fn circular_distance(a: f64, b: f64) -> f64 {
+    let distance = (a - b).rem_euclid(1.0);
+
+    distance.min(1.0 - distance)
+}
+
+#[inline]
+pub fn bell_curve((x, center, width, sharpness): (f64, f64, f64, f64)) -> f64 {
+    (-((x - center).abs() / width).powf(sharpness)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    #[test]
+    fn circular_distance_wraps_around() {
+        assert_close(circular_distance(0.1, 0.3), 0.2);
+        assert_close(circular_distance(0.3, 0.1), 0.2);
+        assert_close(circular_distance(0.05, 0.95), 0.1);
+        assert_close(circular_distance(0.95, 0.05), 0.1);
+        assert_close(circular_distance(0.0, 0.5), 0.5);
+        assert_close(circular_distance(0.25, 0.25), 0.0);
+    }
+
+    #[test]
+    fn circular_distance_outside_the_unit_range() {
+        assert_close(circular_distance(1.1, 0.1), 0.0);
+        assert_close(circular_distance(-0.1, 0.1), 0.2);
+        assert_close(circular_distance(0.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn circular_distance_is_at_most_half() {
+        for i in 0..=20 {
+            for j in 0..=20 {
+                let distance = circular_distance(i as f64 / 20.0, j as f64 / 20.0);
+
+                assert!((0.0..=0.5).contains(&distance), "{i} {j}: {distance}");
+            }
+        }
+    }
+
+    #[test]
+    fn combinator_apply() {
+        assert_eq!(Combinator::Max.apply(0.2, 0.5), 0.5);
+        assert_eq!(Combinator::Min.apply(0.2, 0.5), 0.2);
+        assert_close(Combinator::Add.apply(0.2, 0.5), 0.7);
+        assert_close(Combinator::Multiply.apply(0.2, 0.5), 0.1);
+    }
+}
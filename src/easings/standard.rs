@@ -0,0 +1,466 @@
+use std::{
+    f64::consts::{FRAC_PI_2, PI},
+    fmt,
+};
+
+use bevy::{
+    math::curve::{Curve, EaseFunction, EasingCurve, Interval},
+    reflect::Reflect,
+};
+use serde::Deserialize;
+
+use super::{expr::EasingExpr, Easing};
+
+/// The usual named easing functions, mapping [0, 1] onto [0, 1].
+///
+/// Everything `EaseFunction` can express converts both ways, so the same curve can drive the
+/// scanline and bevy's own tweens. Those variants use bevy's formulas as they are, endpoints
+/// included, so both evaluate to the same values.
+#[derive(Reflect, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub enum StandardEasing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    /// damped oscillation parametrised by its angular frequency `omega`
+    Elastic(f64),
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    SmoothStep,
+    SmootherStep,
+    /// `n` rounded steps between 0 and 1, matching `EaseFunction::Steps`
+    Steps(usize),
+    /// CSS style `cubic-bezier(x1, y1, x2, y2)`, with the curve starting at (0, 0) and ending at
+    /// (1, 1)
+    CubicBezier {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+}
+
+const BACK_C1: f64 = 1.70158;
+const BACK_C2: f64 = BACK_C1 + 1.525;
+const BACK_C3: f64 = BACK_C1 + 1.0;
+
+/// same fit as bevy's `EaseFunction::BounceOut`, so the two stay interchangeable
+fn bounce_out(t: f64) -> f64 {
+    if t < 4.0 / 11.0 {
+        (121.0 * t * t) / 16.0
+    } else if t < 8.0 / 11.0 {
+        (363.0 / 40.0 * t * t) - (99.0 / 10.0 * t) + 17.0 / 5.0
+    } else if t < 9.0 / 10.0 {
+        (4356.0 / 361.0 * t * t) - (35442.0 / 1805.0 * t) + 16061.0 / 1805.0
+    } else {
+        (54.0 / 5.0 * t * t) - (513.0 / 25.0 * t) + 268.0 / 25.0
+    }
+}
+
+/// Solves a CSS cubic bezier for `x`, returning the matching `y`.
+fn cubic_bezier(x: f64, x1: f64, y1: f64, x2: f64, y2: f64) -> f64 {
+    let (x1, x2) = (x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0));
+
+    // polynomial coefficients of one axis of the curve
+    let coefficients = |p1: f64, p2: f64| {
+        let c = 3.0 * p1;
+        let b = 3.0 * (p2 - p1) - c;
+        let a = 1.0 - c - b;
+
+        (a, b, c)
+    };
+
+    let (ax, bx, cx) = coefficients(x1, x2);
+    let (ay, by, cy) = coefficients(y1, y2);
+
+    let sample_x = |t: f64| ((ax * t + bx) * t + cx) * t;
+    let sample_dx = |t: f64| (3.0 * ax * t + 2.0 * bx) * t + cx;
+    let sample_y = |t: f64| ((ay * t + by) * t + cy) * t;
+
+    // newton's method first, which converges in a handful of steps for most curves
+    let mut t = x;
+
+    for _ in 0..8 {
+        let error = sample_x(t) - x;
+
+        if error.abs() < 1e-7 {
+            return sample_y(t);
+        }
+
+        let dx = sample_dx(t);
+
+        if dx.abs() < 1e-6 {
+            break;
+        }
+
+        t -= error / dx;
+    }
+
+    // fall back to bisection for flat sections
+    let (mut lo, mut hi) = (0.0, 1.0);
+    t = x;
+
+    for _ in 0..64 {
+        let value = sample_x(t);
+
+        if (value - x).abs() < 1e-7 {
+            break;
+        }
+
+        if value < x {
+            lo = t;
+        } else {
+            hi = t;
+        }
+
+        t = (lo + hi) / 2.0;
+    }
+
+    sample_y(t)
+}
+
+impl Easing for StandardEasing {
+    fn evaluate(&self, x: f64) -> f64 {
+        let t = x.clamp(0.0, 1.0);
+
+        match *self {
+            Self::Linear => t,
+            Self::QuadIn => t.powi(2),
+            Self::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Self::QuadInOut if t < 0.5 => 2.0 * t.powi(2),
+            Self::QuadInOut => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            Self::CubicIn => t.powi(3),
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut if t < 0.5 => 4.0 * t.powi(3),
+            Self::CubicInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            Self::QuartIn => t.powi(4),
+            Self::QuartOut => 1.0 - (1.0 - t).powi(4),
+            Self::QuartInOut if t < 0.5 => 8.0 * t.powi(4),
+            Self::QuartInOut => 1.0 - (-2.0 * t + 2.0).powi(4) / 2.0,
+            Self::QuintIn => t.powi(5),
+            Self::QuintOut => 1.0 - (1.0 - t).powi(5),
+            Self::QuintInOut if t < 0.5 => 16.0 * t.powi(5),
+            Self::QuintInOut => 1.0 - (-2.0 * t + 2.0).powi(5) / 2.0,
+            Self::SineIn => 1.0 - (t * FRAC_PI_2).cos(),
+            Self::SineOut => (t * FRAC_PI_2).sin(),
+            Self::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Self::ExpoIn => 2f64.powf(10.0 * t - 10.0),
+            Self::ExpoOut => 1.0 - 2f64.powf(-10.0 * t),
+            Self::ExpoInOut if t < 0.5 => 2f64.powf(20.0 * t - 10.0) / 2.0,
+            Self::ExpoInOut => (2.0 - 2f64.powf(-20.0 * t + 10.0)) / 2.0,
+            Self::CircIn => 1.0 - (1.0 - t.powi(2)).sqrt(),
+            Self::CircOut => (1.0 - (t - 1.0).powi(2)).sqrt(),
+            Self::CircInOut if t < 0.5 => (1.0 - (1.0 - (2.0 * t).powi(2)).sqrt()) / 2.0,
+            Self::CircInOut => ((1.0 - (-2.0 * t + 2.0).powi(2)).sqrt() + 1.0) / 2.0,
+            Self::BackIn => BACK_C3 * t.powi(3) - BACK_C1 * t.powi(2),
+            Self::BackOut => 1.0 + BACK_C3 * (t - 1.0).powi(3) + BACK_C1 * (t - 1.0).powi(2),
+            Self::BackInOut if t < 0.5 => {
+                (2.0 * t).powi(2) * ((BACK_C2 + 1.0) * 2.0 * t - BACK_C2) / 2.0
+            }
+            Self::BackInOut => {
+                ((2.0 * t - 2.0).powi(2) * ((BACK_C2 + 1.0) * (2.0 * t - 2.0) + BACK_C2) + 2.0)
+                    / 2.0
+            }
+            Self::ElasticIn => {
+                -(2f64.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+            }
+            Self::ElasticOut => {
+                2f64.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+            }
+            Self::ElasticInOut if t < 0.5 => {
+                -(2f64.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * (2.0 * PI / 4.5)).sin()) / 2.0
+            }
+            Self::ElasticInOut => {
+                2f64.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * (2.0 * PI / 4.5)).sin() / 2.0
+                    + 1.0
+            }
+            Self::Elastic(omega) => {
+                1.0 - (1.0 - t).powi(2) * (2.0 * (omega * t).sin() / omega + (omega * t).cos())
+            }
+            Self::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Self::BounceOut => bounce_out(t),
+            Self::BounceInOut if t < 0.5 => (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0,
+            Self::BounceInOut => (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0,
+            Self::SmoothStep => t * t * (3.0 - 2.0 * t),
+            Self::SmootherStep => t * t * t * (t * (t * 6.0 - 15.0) + 10.0),
+            Self::Steps(steps) => (t * steps as f64).round() / steps.max(1) as f64,
+            Self::CubicBezier { x1, y1, x2, y2 } => cubic_bezier(t, x1, y1, x2, y2),
+        }
+    }
+}
+
+impl From<StandardEasing> for EasingExpr {
+    fn from(easing: StandardEasing) -> Self {
+        Self::Standard(easing)
+    }
+}
+
+impl fmt::Display for StandardEasing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Elastic(omega) => write!(f, "elastic({omega:.2})"),
+            Self::Steps(steps) => write!(f, "steps({steps})"),
+            Self::CubicBezier { x1, y1, x2, y2 } => {
+                write!(f, "cubic_bezier({x1:.2}, {y1:.2}, {x2:.2}, {y2:.2})")
+            }
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// Lets the easing be sampled directly as a bevy curve, including the variants `EaseFunction` has
+/// no equivalent for.
+impl Curve<f32> for StandardEasing {
+    fn domain(&self) -> Interval {
+        Interval::UNIT
+    }
+
+    fn sample_unchecked(&self, t: f32) -> f32 {
+        self.evaluate(t as f64) as f32
+    }
+}
+
+impl StandardEasing {
+    /// A bevy `EasingCurve` between `start` and `end`, if `EaseFunction` can express this easing.
+    pub fn curve<T>(self, start: T, end: T) -> Option<EasingCurve<T>> {
+        EaseFunction::try_from(self)
+            .ok()
+            .map(|ease_fn| EasingCurve::new(start, end, ease_fn))
+    }
+}
+
+impl From<EaseFunction> for StandardEasing {
+    fn from(ease_fn: EaseFunction) -> Self {
+        match ease_fn {
+            EaseFunction::Linear => Self::Linear,
+            EaseFunction::QuadraticIn => Self::QuadIn,
+            EaseFunction::QuadraticOut => Self::QuadOut,
+            EaseFunction::QuadraticInOut => Self::QuadInOut,
+            EaseFunction::CubicIn => Self::CubicIn,
+            EaseFunction::CubicOut => Self::CubicOut,
+            EaseFunction::CubicInOut => Self::CubicInOut,
+            EaseFunction::QuarticIn => Self::QuartIn,
+            EaseFunction::QuarticOut => Self::QuartOut,
+            EaseFunction::QuarticInOut => Self::QuartInOut,
+            EaseFunction::QuinticIn => Self::QuintIn,
+            EaseFunction::QuinticOut => Self::QuintOut,
+            EaseFunction::QuinticInOut => Self::QuintInOut,
+            EaseFunction::SineIn => Self::SineIn,
+            EaseFunction::SineOut => Self::SineOut,
+            EaseFunction::SineInOut => Self::SineInOut,
+            EaseFunction::CircularIn => Self::CircIn,
+            EaseFunction::CircularOut => Self::CircOut,
+            EaseFunction::CircularInOut => Self::CircInOut,
+            EaseFunction::ExponentialIn => Self::ExpoIn,
+            EaseFunction::ExponentialOut => Self::ExpoOut,
+            EaseFunction::ExponentialInOut => Self::ExpoInOut,
+            EaseFunction::ElasticIn => Self::ElasticIn,
+            EaseFunction::ElasticOut => Self::ElasticOut,
+            EaseFunction::ElasticInOut => Self::ElasticInOut,
+            EaseFunction::BackIn => Self::BackIn,
+            EaseFunction::BackOut => Self::BackOut,
+            EaseFunction::BackInOut => Self::BackInOut,
+            EaseFunction::BounceIn => Self::BounceIn,
+            EaseFunction::BounceOut => Self::BounceOut,
+            EaseFunction::BounceInOut => Self::BounceInOut,
+            EaseFunction::Steps(steps) => Self::Steps(steps),
+            EaseFunction::Elastic(omega) => Self::Elastic(omega as f64),
+        }
+    }
+}
+
+/// Returned when converting an easing that `EaseFunction` has no variant for.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UnsupportedEaseFunction(pub StandardEasing);
+
+impl fmt::Display for UnsupportedEaseFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bevy's EaseFunction has no equivalent of {}", self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedEaseFunction {}
+
+impl TryFrom<StandardEasing> for EaseFunction {
+    type Error = UnsupportedEaseFunction;
+
+    fn try_from(easing: StandardEasing) -> Result<Self, Self::Error> {
+        Ok(match easing {
+            StandardEasing::Linear => Self::Linear,
+            StandardEasing::QuadIn => Self::QuadraticIn,
+            StandardEasing::QuadOut => Self::QuadraticOut,
+            StandardEasing::QuadInOut => Self::QuadraticInOut,
+            StandardEasing::CubicIn => Self::CubicIn,
+            StandardEasing::CubicOut => Self::CubicOut,
+            StandardEasing::CubicInOut => Self::CubicInOut,
+            StandardEasing::QuartIn => Self::QuarticIn,
+            StandardEasing::QuartOut => Self::QuarticOut,
+            StandardEasing::QuartInOut => Self::QuarticInOut,
+            StandardEasing::QuintIn => Self::QuinticIn,
+            StandardEasing::QuintOut => Self::QuinticOut,
+            StandardEasing::QuintInOut => Self::QuinticInOut,
+            StandardEasing::SineIn => Self::SineIn,
+            StandardEasing::SineOut => Self::SineOut,
+            StandardEasing::SineInOut => Self::SineInOut,
+            StandardEasing::ExpoIn => Self::ExponentialIn,
+            StandardEasing::ExpoOut => Self::ExponentialOut,
+            StandardEasing::ExpoInOut => Self::ExponentialInOut,
+            StandardEasing::CircIn => Self::CircularIn,
+            StandardEasing::CircOut => Self::CircularOut,
+            StandardEasing::CircInOut => Self::CircularInOut,
+            StandardEasing::BackIn => Self::BackIn,
+            StandardEasing::BackOut => Self::BackOut,
+            StandardEasing::BackInOut => Self::BackInOut,
+            StandardEasing::ElasticIn => Self::ElasticIn,
+            StandardEasing::ElasticOut => Self::ElasticOut,
+            StandardEasing::ElasticInOut => Self::ElasticInOut,
+            StandardEasing::Elastic(omega) => Self::Elastic(omega as f32),
+            StandardEasing::BounceIn => Self::BounceIn,
+            StandardEasing::BounceOut => Self::BounceOut,
+            StandardEasing::BounceInOut => Self::BounceInOut,
+            StandardEasing::Steps(steps) => Self::Steps(steps),
+            StandardEasing::SmoothStep
+            | StandardEasing::SmootherStep
+            | StandardEasing::CubicBezier { .. } => return Err(UnsupportedEaseFunction(easing)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [StandardEasing; 35] = [
+        StandardEasing::Linear,
+        StandardEasing::QuadIn,
+        StandardEasing::QuadOut,
+        StandardEasing::QuadInOut,
+        StandardEasing::CubicIn,
+        StandardEasing::CubicOut,
+        StandardEasing::CubicInOut,
+        StandardEasing::QuartIn,
+        StandardEasing::QuartOut,
+        StandardEasing::QuartInOut,
+        StandardEasing::QuintIn,
+        StandardEasing::QuintOut,
+        StandardEasing::QuintInOut,
+        StandardEasing::SineIn,
+        StandardEasing::SineOut,
+        StandardEasing::SineInOut,
+        StandardEasing::ExpoIn,
+        StandardEasing::ExpoOut,
+        StandardEasing::ExpoInOut,
+        StandardEasing::CircIn,
+        StandardEasing::CircOut,
+        StandardEasing::CircInOut,
+        StandardEasing::BackIn,
+        StandardEasing::BackOut,
+        StandardEasing::BackInOut,
+        StandardEasing::ElasticIn,
+        StandardEasing::ElasticOut,
+        StandardEasing::ElasticInOut,
+        StandardEasing::Elastic(10.0),
+        StandardEasing::BounceIn,
+        StandardEasing::BounceOut,
+        StandardEasing::BounceInOut,
+        StandardEasing::Steps(3),
+        StandardEasing::SmoothStep,
+        StandardEasing::CubicBezier {
+            x1: 0.25,
+            y1: 0.1,
+            x2: 0.25,
+            y2: 1.0,
+        },
+    ];
+
+    #[test]
+    fn matches_bevy() {
+        for easing in ALL {
+            let Ok(ease_fn) = EaseFunction::try_from(easing) else {
+                continue;
+            };
+
+            let curve = EasingCurve::new(0.0, 1.0, ease_fn);
+
+            for i in 0..=100 {
+                let t = i as f32 / 100.0;
+                let expected = curve.sample(t).unwrap();
+                let actual = easing.evaluate(t as f64) as f32;
+
+                assert!(
+                    (actual - expected).abs() < 1e-5,
+                    "{easing} at {t}: {actual} != {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn converts_both_ways() {
+        for easing in ALL {
+            if let Ok(ease_fn) = EaseFunction::try_from(easing) {
+                assert_eq!(StandardEasing::from(ease_fn), easing);
+            }
+        }
+
+        assert!(EaseFunction::try_from(StandardEasing::SmootherStep).is_err());
+        assert!(StandardEasing::SmoothStep.curve(0.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn back_in_out_overshoots_like_bevy() {
+        let easing = StandardEasing::BackInOut;
+
+        // the overshoot of bevy's `back_in_out` with `c2 = 1.70158 + 1.525`
+        assert!(easing.evaluate(0.2) < -0.1);
+        assert!(easing.evaluate(0.8) > 1.1);
+        assert!((easing.evaluate(0.5) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cubic_bezier() {
+        let linear = StandardEasing::CubicBezier {
+            x1: 0.0,
+            y1: 0.0,
+            x2: 1.0,
+            y2: 1.0,
+        };
+
+        for i in 0..=10 {
+            let t = i as f64 / 10.0;
+
+            assert!((linear.evaluate(t) - t).abs() < 1e-6);
+        }
+
+        let ease = ALL[ALL.len() - 1];
+
+        assert!(ease.evaluate(0.0).abs() < 1e-6);
+        assert!((ease.evaluate(1.0) - 1.0).abs() < 1e-6);
+        assert!(ease.evaluate(0.5) > 0.5);
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use super::position::GridPosition;
+
+pub const DEFAULT_GRID_WIDTH: i32 = 21;
+pub const DEFAULT_GRID_HEIGHT: i32 = 11;
+pub const DEFAULT_PIXEL_SIZE: f32 = 56.0;
+pub const DEFAULT_PIXEL_GAP: f32 = 5.0;
+
+/// Layout of the pixel grid. Changing this while in `SceneState::Game` rebuilds the grid.
+#[derive(Resource, Reflect, Copy, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct GridConfig {
+    pub width: i32,
+    pub height: i32,
+    pub pixel_size: f32,
+    pub gap: f32,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_GRID_WIDTH,
+            height: DEFAULT_GRID_HEIGHT,
+            pixel_size: DEFAULT_PIXEL_SIZE,
+            gap: DEFAULT_PIXEL_GAP,
+        }
+    }
+}
+
+impl GridConfig {
+    /// whether the grid has at least one cell
+    pub const fn is_valid(&self) -> bool {
+        self.width >= 1 && self.height >= 1
+    }
+
+    /// total number of cells in the grid
+    pub const fn packed_size(&self) -> i32 {
+        self.width * self.height
+    }
+
+    pub const fn position(&self, x: i32, y: i32) -> GridPosition {
+        GridPosition::new(self.width, self.height, x, y)
+    }
+
+    pub const fn center(&self) -> GridPosition {
+        self.position(self.width / 2, self.height / 2)
+    }
+
+    /// distance between the centers of two neighbouring pixels
+    pub fn stride(&self) -> f32 {
+        self.pixel_size + self.gap
+    }
+
+    /// whether the pixels spawned for `other` can be reused for `self`
+    pub fn same_layout(&self, other: &GridConfig) -> bool {
+        self.width == other.width && self.height == other.height
+    }
+
+    /// maps a position from another grid onto this one, clamping coordinates that no longer fit.
+    /// Positions on an empty grid map to the first cell.
+    pub fn remap(&self, pos: GridPosition) -> GridPosition {
+        if pos.width < 1 || pos.height < 1 {
+            return self.position(0, 0);
+        }
+
+        let coords = pos.unpacked();
+
+        self.position(
+            coords.x.clamp(0, self.width - 1),
+            coords.y.clamp(0, self.height - 1),
+        )
+    }
+}
+
+/// Grows an empty `GridConfig` back to at least one cell, so nothing downstream has to deal with
+/// a grid it can't index into. Runs before state transitions, so the game scene is never set up
+/// with an invalid config.
+pub(super) fn validate_grid_config(mut config: ResMut<GridConfig>) {
+    if config.is_valid() {
+        return;
+    }
+
+    warn!(
+        "invalid grid size: {}x{}, clamping to at least 1x1",
+        config.width, config.height
+    );
+
+    config.width = config.width.max(1);
+    config.height = config.height.max(1);
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+
+use crate::easings::{bell_curve, expr::BellShape};
+
+use super::position::{DistanceMetric, GridPosition};
+
+/// Slows the scanline based on the grid distance between the cursor and each user pixel, rather
+/// than how far apart they are in the scan order.
+///
+/// Distances are normalised by the largest distance across the grid, so bell widths mean the same
+/// thing here as they do along the scan.
+#[derive(Component, Default, Debug, Clone)]
+pub struct InfluenceField {
+    metric: DistanceMetric,
+    max_distance: f64,
+    sources: Vec<(IVec2, BellShape)>,
+}
+
+impl InfluenceField {
+    pub fn new(
+        metric: DistanceMetric,
+        width: i32,
+        height: i32,
+        sources: impl IntoIterator<Item = (GridPosition, BellShape)>,
+    ) -> Self {
+        Self {
+            metric,
+            max_distance: metric.max_distance(width, height).max(1.0),
+            sources: sources
+                .into_iter()
+                .map(|(pos, bell)| (pos.unpacked(), bell))
+                .collect(),
+        }
+    }
+
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// strongest influence of any user pixel on `pos`, or 0.0 if there are none
+    pub fn sample(&self, pos: &GridPosition) -> f64 {
+        let coords = pos.unpacked();
+
+        self.sources
+            .iter()
+            .map(|(source, bell)| {
+                let distance = self.metric.distance(coords, *source) / self.max_distance;
+
+                bell_curve((distance, 0.0, bell.width, bell.sharpness))
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METRICS: [DistanceMetric; 3] = [
+        DistanceMetric::Euclidean,
+        DistanceMetric::Manhattan,
+        DistanceMetric::Chebyshev,
+    ];
+
+    /// `e^-distance`, so samples read back the normalised distance directly
+    const LINEAR_BELL: BellShape = BellShape {
+        width: 1.0,
+        sharpness: 1.0,
+    };
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    fn field(
+        metric: DistanceMetric,
+        width: i32,
+        height: i32,
+        sources: &[(i32, i32)],
+    ) -> InfluenceField {
+        InfluenceField::new(
+            metric,
+            width,
+            height,
+            sources
+                .iter()
+                .map(|&(x, y)| (GridPosition::new(width, height, x, y), LINEAR_BELL)),
+        )
+    }
+
+    #[test]
+    fn no_sources_is_zero() {
+        for metric in METRICS {
+            let field = field(metric, 4, 3, &[]);
+
+            assert!(field.is_empty());
+            assert_eq!(field.metric(), metric);
+
+            for packed in 0..12 {
+                let pos = GridPosition {
+                    width: 4,
+                    height: 3,
+                    packed,
+                };
+
+                assert_eq!(field.sample(&pos), 0.0);
+            }
+        }
+
+        assert!(InfluenceField::default().is_empty());
+    }
+
+    #[test]
+    fn peaks_at_each_source() {
+        for metric in METRICS {
+            let field = field(metric, 5, 5, &[(1, 1), (3, 4)]);
+
+            assert!(!field.is_empty());
+            assert_close(field.sample(&GridPosition::new(5, 5, 1, 1)), 1.0);
+            assert_close(field.sample(&GridPosition::new(5, 5, 3, 4)), 1.0);
+        }
+    }
+
+    #[test]
+    fn sample_follows_the_metric() {
+        let pos = GridPosition::new(5, 5, 3, 4);
+
+        // from (0, 0) to (3, 4), over the distance from (0, 0) to (4, 4)
+        for (metric, distance) in [
+            (DistanceMetric::Euclidean, 5.0 / 32_f64.sqrt()),
+            (DistanceMetric::Manhattan, 7.0 / 8.0),
+            (DistanceMetric::Chebyshev, 4.0 / 4.0),
+        ] {
+            let field = field(metric, 5, 5, &[(0, 0)]);
+
+            assert_close(field.sample(&pos), (-distance).exp());
+        }
+    }
+
+    #[test]
+    fn opposite_corners_are_a_full_distance_apart() {
+        for metric in METRICS {
+            for (width, height) in [(5, 5), (8, 3), (1, 6), (2, 2)] {
+                let field = field(metric, width, height, &[(0, 0)]);
+                let corner = GridPosition::new(width, height, width - 1, height - 1);
+
+                assert_close(field.sample(&corner), (-1.0_f64).exp());
+            }
+        }
+    }
+
+    #[test]
+    fn single_cell_grid_does_not_divide_by_zero() {
+        for metric in METRICS {
+            let field = field(metric, 1, 1, &[(0, 0)]);
+
+            assert_close(field.sample(&GridPosition::new(1, 1, 0, 0)), 1.0);
+        }
+    }
+
+    #[test]
+    fn strongest_source_wins() {
+        let narrow = BellShape {
+            width: 0.1,
+            sharpness: 2.0,
+        };
+        let field = InfluenceField::new(
+            DistanceMetric::Chebyshev,
+            5,
+            5,
+            [
+                (GridPosition::new(5, 5, 0, 0), LINEAR_BELL),
+                (GridPosition::new(5, 5, 4, 0), narrow),
+            ],
+        );
+
+        // (3, 0) is closer to the narrow bell, but the wide one reaches further
+        let pos = GridPosition::new(5, 5, 3, 0);
+        let wide = (-0.75_f64).exp();
+        let narrow = (-(0.25_f64 / 0.1).powi(2)).exp();
+
+        assert!(wide > narrow);
+        assert_close(field.sample(&pos), wide);
+        assert_close(field.sample(&GridPosition::new(5, 5, 4, 0)), 1.0);
+    }
+}
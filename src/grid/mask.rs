@@ -0,0 +1,368 @@
+use std::fmt;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    color::Luminance,
+    prelude::*,
+};
+
+use super::position::GridPosition;
+
+/// Which cells of a `width` x `height` grid have a pixel, so grids can take any shape.
+///
+/// Cells outside of the mask's own size are always missing.
+#[derive(Asset, Resource, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Resource)]
+pub struct GridMask {
+    width: i32,
+    height: i32,
+    /// one bit per cell, in packed order
+    bits: Vec<u64>,
+}
+
+impl GridMask {
+    pub fn full(width: i32, height: i32) -> Self {
+        let mut mask = Self::empty(width, height);
+
+        for packed in 0..width * height {
+            mask.set_packed(packed, true);
+        }
+
+        mask
+    }
+
+    pub fn empty(width: i32, height: i32) -> Self {
+        let (width, height) = (width.max(0), height.max(0));
+
+        Self {
+            width,
+            height,
+            bits: vec![0; ((width * height) as usize).div_ceil(64)],
+        }
+    }
+
+    /// A layout with one line per row, where `#` marks a cell and `.` or a space marks a hole.
+    /// Short lines are padded with holes.
+    ///
+    /// ```text
+    /// .###.
+    /// #...#
+    /// .###.
+    /// ```
+    pub fn from_text(layout: &str) -> Result<Self, GridMaskError> {
+        let rows = layout
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect::<Vec<_>>();
+
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut mask = Self::empty(width as i32, rows.len() as i32);
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                match cell {
+                    '#' => mask.set(IVec2::new(x as i32, y as i32), true),
+                    '.' | ' ' => {}
+                    _ => {
+                        return Err(GridMaskError::UnknownCell {
+                            cell,
+                            line: y + 1,
+                            column: x + 1,
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(mask)
+    }
+
+    /// One cell per image pixel. Opaque, light pixels are cells and everything else is a hole.
+    pub fn from_image(image: &Image) -> Self {
+        let size = image.size().as_ivec2();
+        let mut mask = Self::empty(size.x, size.y);
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let Ok(color) = image.get_color_at(x as u32, y as u32) else {
+                    continue;
+                };
+
+                if color.alpha() >= 0.5 && color.luminance() >= 0.5 {
+                    mask.set(IVec2::new(x, y), true);
+                }
+            }
+        }
+
+        mask
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// whether the cell at `coords` exists
+    pub fn get(&self, coords: IVec2) -> bool {
+        GridPosition::contains(self.width, self.height, coords)
+            && self.get_packed(GridPosition::pack(self.width, coords.x, coords.y))
+    }
+
+    pub fn contains(&self, pos: &GridPosition) -> bool {
+        self.get(pos.unpacked())
+    }
+
+    pub fn set(&mut self, coords: IVec2, present: bool) {
+        if GridPosition::contains(self.width, self.height, coords) {
+            self.set_packed(GridPosition::pack(self.width, coords.x, coords.y), present);
+        }
+    }
+
+    fn get_packed(&self, packed: i32) -> bool {
+        let packed = packed as usize;
+
+        self.bits[packed / 64] & (1 << (packed % 64)) != 0
+    }
+
+    fn set_packed(&mut self, packed: i32, present: bool) {
+        let packed = packed as usize;
+
+        if present {
+            self.bits[packed / 64] |= 1 << (packed % 64);
+        } else {
+            self.bits[packed / 64] &= !(1 << (packed % 64));
+        }
+    }
+
+    /// number of cells that exist
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+
+    /// the cells that exist, as positions on a `width` x `height` grid
+    pub fn cells(&self, width: i32, height: i32) -> impl Iterator<Item = GridPosition> + '_ {
+        GridPosition::all(width, height).filter(|pos| self.contains(pos))
+    }
+
+    /// `pos` if its cell exists, otherwise the closest cell that does (the first in packed order
+    /// on ties). `None` if the mask has no cells on `pos`'s grid.
+    pub fn nearest(&self, pos: &GridPosition) -> Option<GridPosition> {
+        if self.contains(pos) {
+            return Some(*pos);
+        }
+
+        self.cells(pos.width, pos.height).min_by(|a, b| {
+            a.euclidean_distance(pos)
+                .total_cmp(&b.euclidean_distance(pos))
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum GridMaskError {
+    Io(std::io::Error),
+    Utf8(std::str::Utf8Error),
+    UnknownCell {
+        cell: char,
+        line: usize,
+        column: usize,
+    },
+}
+
+impl fmt::Display for GridMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read grid mask: {err}"),
+            Self::Utf8(err) => write!(f, "grid mask is not valid UTF-8: {err}"),
+            Self::UnknownCell { cell, line, column } => write!(
+                f,
+                "unknown cell {cell:?} at {line}:{column}, expected '#', '.' or ' '"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GridMaskError {}
+
+impl From<std::io::Error> for GridMaskError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<std::str::Utf8Error> for GridMaskError {
+    fn from(err: std::str::Utf8Error) -> Self {
+        Self::Utf8(err)
+    }
+}
+
+/// Loads `.mask.txt` layouts with [`GridMask::from_text`]. Image masks are loaded as regular
+/// images and converted with [`GridMask::from_image`].
+#[derive(Default)]
+pub struct GridMaskLoader;
+
+impl AssetLoader for GridMaskLoader {
+    type Asset = GridMask;
+    type Settings = ();
+    type Error = GridMaskError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        GridMask::from_text(std::str::from_utf8(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["mask.txt"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    };
+
+    use crate::grid::scan_pattern::{ScanOrder, ScanPatternKind};
+
+    use super::*;
+
+    fn cells(mask: &GridMask) -> Vec<IVec2> {
+        mask.cells(mask.width(), mask.height())
+            .map(|pos| pos.unpacked())
+            .collect()
+    }
+
+    #[test]
+    fn from_text() {
+        let mask = GridMask::from_text(".#.\r\n# #\n##").unwrap();
+
+        assert_eq!((mask.width(), mask.height()), (3, 3));
+        assert_eq!(mask.count(), 5);
+        assert_eq!(
+            cells(&mask),
+            [
+                IVec2::new(1, 0),
+                IVec2::new(0, 1),
+                IVec2::new(2, 1),
+                IVec2::new(0, 2),
+                IVec2::new(1, 2),
+            ]
+        );
+
+        // short lines are padded with holes
+        assert!(!mask.get(IVec2::new(2, 2)));
+        assert!(!mask.get(IVec2::new(3, 0)));
+    }
+
+    #[test]
+    fn from_text_rejects_unknown_cells() {
+        let err = GridMask::from_text("##\n#x").unwrap_err();
+
+        assert!(matches!(
+            err,
+            GridMaskError::UnknownCell {
+                cell: 'x',
+                line: 2,
+                column: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn from_text_empty() {
+        let mask = GridMask::from_text("").unwrap();
+
+        assert_eq!((mask.width(), mask.height()), (0, 0));
+        assert_eq!(mask.count(), 0);
+    }
+
+    #[test]
+    fn from_image() {
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: 2,
+                height: 2,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+
+        image.set_color_at(0, 0, Color::WHITE).unwrap();
+        image
+            .set_color_at(1, 0, Color::srgba(1.0, 1.0, 1.0, 0.0))
+            .unwrap();
+        image
+            .set_color_at(1, 1, Color::srgb(1.0, 0.0, 0.0))
+            .unwrap();
+
+        let mask = GridMask::from_image(&image);
+
+        assert_eq!((mask.width(), mask.height()), (2, 2));
+        assert_eq!(cells(&mask), [IVec2::new(0, 0)]);
+    }
+
+    #[test]
+    fn nearest_cell() {
+        let mask = GridMask::from_text("#...\n....\n..##").unwrap();
+        let pos = |x, y| GridPosition::new(4, 3, x, y);
+
+        assert_eq!(mask.nearest(&pos(0, 0)), Some(pos(0, 0)));
+        assert_eq!(mask.nearest(&pos(1, 0)), Some(pos(0, 0)));
+        assert_eq!(mask.nearest(&pos(3, 1)), Some(pos(3, 2)));
+
+        // (0, 0) and (2, 2) are both two cells from (1, 1), and (0, 0) comes first
+        assert_eq!(mask.nearest(&pos(1, 1)), Some(pos(0, 0)));
+
+        assert_eq!(GridMask::empty(4, 3).nearest(&pos(1, 1)), None);
+    }
+
+    #[test]
+    fn masked_scan_order_skips_missing_cells() {
+        let mask = GridMask::from_text("#.#\n.#.").unwrap();
+        let order = ScanOrder::masked(&ScanPatternKind::RowMajor, 3, 2, &mask);
+        let pos = |x, y| GridPosition::new(3, 2, x, y);
+
+        assert_eq!(order.len(), 3);
+        assert_eq!(
+            (0..3).map(|step| order.packed_at(step)).collect::<Vec<_>>(),
+            [0, 2, 4]
+        );
+
+        assert_eq!(order.step_of(&pos(0, 0)), 0);
+        assert_eq!(order.step_of(&pos(2, 0)), 1);
+        assert_eq!(order.step_of(&pos(1, 1)), 2);
+
+        // missing cells map to the step of the next cell that is scanned, wrapping at the end
+        assert_eq!(order.step_of(&pos(1, 0)), 1);
+        assert_eq!(order.step_of(&pos(0, 1)), 2);
+        assert_eq!(order.step_of(&pos(2, 1)), 0);
+    }
+
+    #[test]
+    fn masked_scan_order_without_cells_is_unmasked() {
+        let order = ScanOrder::masked(&ScanPatternKind::Serpentine, 3, 2, &GridMask::empty(3, 2));
+
+        assert_eq!(order.len(), 6);
+    }
+}
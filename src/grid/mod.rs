@@ -1 +1,20 @@
+pub mod config;
+pub mod field;
+pub mod mask;
 pub mod position;
+pub mod scan_pattern;
+
+use bevy::prelude::*;
+use config::{validate_grid_config, GridConfig};
+use mask::{GridMask, GridMaskLoader};
+
+pub fn plugin(app: &mut App) {
+    app.register_type::<GridMask>();
+    app.init_asset::<GridMask>();
+    app.init_asset_loader::<GridMaskLoader>();
+
+    app.add_systems(
+        PreUpdate,
+        validate_grid_config.run_if(resource_exists_and_changed::<GridConfig>),
+    );
+}
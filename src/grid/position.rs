@@ -2,6 +2,48 @@ use bevy::prelude::*;
 use bevy::{math::IVec2, reflect::Reflect};
 use std::hash::Hash;
 
+/// How the distance between two cells is measured.
+#[derive(Reflect, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DistanceMetric {
+    #[default]
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    pub fn distance(self, a: IVec2, b: IVec2) -> f64 {
+        let delta = (a - b).abs();
+
+        match self {
+            Self::Euclidean => (delta.as_dvec2()).length(),
+            Self::Manhattan => (delta.x + delta.y) as f64,
+            Self::Chebyshev => delta.max_element() as f64,
+        }
+    }
+
+    /// the largest distance between any two cells of a `width` x `height` grid
+    pub fn max_distance(self, width: i32, height: i32) -> f64 {
+        self.distance(IVec2::ZERO, IVec2::new(width - 1, height - 1))
+    }
+}
+
+/// Offsets to the cells sharing an edge with a cell.
+pub const NEIGHBOURS_4: [IVec2; 4] = [IVec2::NEG_Y, IVec2::X, IVec2::Y, IVec2::NEG_X];
+
+/// Offsets to the cells sharing an edge or corner with a cell, clockwise from the top left.
+pub const NEIGHBOURS_8: [IVec2; 8] = [
+    IVec2::new(-1, -1),
+    IVec2::NEG_Y,
+    IVec2::new(1, -1),
+    IVec2::X,
+    IVec2::new(1, 1),
+    IVec2::Y,
+    IVec2::new(-1, 1),
+    IVec2::NEG_X,
+];
+
+/// A cell of a `width` x `height` grid, packed row by row.
 #[derive(Reflect, Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Hash)]
 pub struct GridPosition {
     pub width: i32,
@@ -18,10 +60,27 @@ impl GridPosition {
         }
     }
 
+    /// `None` if `coords` lies outside of the grid
+    pub fn checked_new(width: i32, height: i32, coords: IVec2) -> Option<Self> {
+        Self::contains(width, height, coords).then(|| Self::new(width, height, coords.x, coords.y))
+    }
+
+    pub const fn contains(width: i32, height: i32, coords: IVec2) -> bool {
+        coords.x >= 0 && coords.x < width && coords.y >= 0 && coords.y < height
+    }
+
     pub const fn unpacked(&self) -> IVec2 {
         Self::unpack(self.width, self.height, self.packed)
     }
 
+    pub const fn x(&self) -> i32 {
+        self.packed % self.width
+    }
+
+    pub const fn y(&self) -> i32 {
+        self.packed / self.width
+    }
+
     pub fn replace(&mut self, pos: i32) {
         self.packed = pos;
     }
@@ -30,15 +89,335 @@ impl GridPosition {
         self.packed = Self::pack(self.width, new_x, new_y);
     }
 
+    /// the same grid, at `coords`
+    fn with_coords(&self, coords: IVec2) -> Self {
+        Self::new(self.width, self.height, coords.x, coords.y)
+    }
+
+    /// `None` if moving by `offset` leaves the grid
+    pub fn checked_offset(&self, offset: IVec2) -> Option<Self> {
+        Self::checked_new(self.width, self.height, self.unpacked() + offset)
+    }
+
+    /// moves by `offset`, coming back in on the opposite edge when it leaves the grid
+    pub fn wrapping_offset(&self, offset: IVec2) -> Self {
+        let coords = self.unpacked() + offset;
+
+        self.with_coords(IVec2::new(
+            coords.x.rem_euclid(self.width),
+            coords.y.rem_euclid(self.height),
+        ))
+    }
+
+    /// moves by `offset`, stopping at the edges of the grid
+    pub fn clamped_offset(&self, offset: IVec2) -> Self {
+        let coords = self.unpacked() + offset;
+
+        self.with_coords(coords.clamp(IVec2::ZERO, IVec2::new(self.width - 1, self.height - 1)))
+    }
+
+    /// the edge-adjacent cells that are inside the grid
+    pub fn neighbours_4(&self) -> impl Iterator<Item = Self> + '_ {
+        NEIGHBOURS_4
+            .into_iter()
+            .filter_map(|offset| self.checked_offset(offset))
+    }
+
+    /// the edge- and corner-adjacent cells that are inside the grid
+    pub fn neighbours_8(&self) -> impl Iterator<Item = Self> + '_ {
+        NEIGHBOURS_8
+            .into_iter()
+            .filter_map(|offset| self.checked_offset(offset))
+    }
+
+    pub fn distance(&self, other: &GridPosition, metric: DistanceMetric) -> f64 {
+        metric.distance(self.unpacked(), other.unpacked())
+    }
+
+    pub fn manhattan_distance(&self, other: &GridPosition) -> i32 {
+        let delta = (self.unpacked() - other.unpacked()).abs();
+
+        delta.x + delta.y
+    }
+
+    pub fn chebyshev_distance(&self, other: &GridPosition) -> i32 {
+        (self.unpacked() - other.unpacked()).abs().max_element()
+    }
+
+    pub fn euclidean_distance(&self, other: &GridPosition) -> f64 {
+        self.distance(other, DistanceMetric::Euclidean)
+    }
+
+    /// position in the packed order, in [0, 1)
     pub fn normalised(&self) -> f64 {
-        self.packed as f64 / Self::pack(self.width, self.width, self.height) as f64
+        self.packed as f64 / (self.width * self.height) as f64
+    }
+
+    /// every cell of the grid, row by row
+    pub fn all(width: i32, height: i32) -> impl Iterator<Item = Self> {
+        (0..width * height).map(move |packed| Self {
+            width,
+            height,
+            packed,
+        })
+    }
+
+    /// the cells of row `y`, left to right
+    pub fn row(width: i32, height: i32, y: i32) -> impl Iterator<Item = Self> {
+        let y_in_grid = (0..height).contains(&y);
+
+        (0..width)
+            .filter(move |_| y_in_grid)
+            .map(move |x| Self::new(width, height, x, y))
+    }
+
+    /// the cells of column `x`, top to bottom
+    pub fn column(width: i32, height: i32, x: i32) -> impl Iterator<Item = Self> {
+        let x_in_grid = (0..width).contains(&x);
+
+        (0..height)
+            .filter(move |_| x_in_grid)
+            .map(move |y| Self::new(width, height, x, y))
+    }
+
+    /// The cells between `min` and `max` (inclusive) row by row, skipping any outside of the
+    /// grid.
+    pub fn rect(width: i32, height: i32, min: IVec2, max: IVec2) -> impl Iterator<Item = Self> {
+        let min = min.max(IVec2::ZERO);
+        let max = max.min(IVec2::new(width - 1, height - 1));
+
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| Self::new(width, height, x, y)))
     }
 
     pub const fn pack(width: i32, x: i32, y: i32) -> i32 {
         (y * width) + x
     }
 
-    pub const fn unpack(width: i32, height: i32, pos: i32) -> IVec2 {
-        IVec2::new(pos / width, pos % height)
+    pub const fn unpack(width: i32, _height: i32, pos: i32) -> IVec2 {
+        IVec2::new(pos % width, pos / width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// largest width and height swept by the exhaustive tests
+    const MAX_SIZE: i32 = 16;
+
+    /// every grid size from 1x1 up to `MAX_SIZE` x `MAX_SIZE`
+    fn sizes() -> impl Iterator<Item = (i32, i32)> {
+        (1..=MAX_SIZE).flat_map(|width| (1..=MAX_SIZE).map(move |height| (width, height)))
+    }
+
+    #[test]
+    fn coords_round_trip() {
+        for (width, height) in sizes() {
+            let cells = (width * height) as f64;
+
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = GridPosition::new(width, height, x, y);
+                    let at = format!("({x}, {y}) on {width}x{height}");
+
+                    assert_eq!(pos.packed, y * width + x, "{at}");
+                    assert_eq!((pos.x(), pos.y()), (x, y), "{at}");
+                    assert_eq!(pos.unpacked(), IVec2::new(x, y), "{at}");
+                    assert_eq!(
+                        GridPosition::unpack(width, height, pos.packed),
+                        IVec2::new(x, y),
+                        "{at}"
+                    );
+                    assert_eq!(
+                        GridPosition::pack(width, pos.x(), pos.y()),
+                        pos.packed,
+                        "{at}"
+                    );
+                    assert_eq!(
+                        GridPosition::checked_new(width, height, IVec2::new(x, y)),
+                        Some(pos),
+                        "{at}"
+                    );
+                    assert_eq!(pos.normalised(), pos.packed as f64 / cells, "{at}");
+                    assert!((0.0..1.0).contains(&pos.normalised()), "{at}");
+                }
+            }
+        }
+    }
+
+    /// moves one coordinate by `offset` a cell at a time, coming back in on the other side
+    fn wrap_by_steps(coord: i32, offset: i32, size: i32) -> i32 {
+        let mut coord = coord;
+
+        for _ in 0..offset.abs() {
+            coord += offset.signum();
+
+            if coord < 0 {
+                coord = size - 1;
+            } else if coord == size {
+                coord = 0;
+            }
+        }
+
+        coord
+    }
+
+    #[test]
+    fn offsets_stay_in_the_grid() {
+        for (width, height) in sizes() {
+            let offsets_x = [-2 * width - 1, -width, -1, 0, 1, width, 3 * width + 2];
+            let offsets_y = [-2 * height - 1, -height, -1, 0, 1, height, 3 * height + 2];
+
+            for pos in GridPosition::all(width, height) {
+                let IVec2 { x, y } = pos.unpacked();
+
+                for dx in offsets_x {
+                    for dy in offsets_y {
+                        let offset = IVec2::new(dx, dy);
+                        let at = format!("({x}, {y}) + {offset} on {width}x{height}");
+
+                        let wrapped = pos.wrapping_offset(offset);
+                        assert_eq!((wrapped.width, wrapped.height), (width, height), "{at}");
+                        assert_eq!(
+                            wrapped.unpacked(),
+                            IVec2::new(wrap_by_steps(x, dx, width), wrap_by_steps(y, dy, height)),
+                            "{at}"
+                        );
+
+                        let clamped = pos.clamped_offset(offset);
+                        assert_eq!((clamped.width, clamped.height), (width, height), "{at}");
+                        assert_eq!(
+                            clamped.unpacked(),
+                            IVec2::new(
+                                (x + dx).max(0).min(width - 1),
+                                (y + dy).max(0).min(height - 1)
+                            ),
+                            "{at}"
+                        );
+
+                        let inside =
+                            (0..width).contains(&(x + dx)) && (0..height).contains(&(y + dy));
+                        assert_eq!(pos.checked_offset(offset).is_some(), inside, "{at}");
+
+                        if inside {
+                            assert_eq!(pos.checked_offset(offset), Some(wrapped), "{at}");
+                            assert_eq!(wrapped, clamped, "{at}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn all_covers_the_grid_in_order() {
+        for (width, height) in sizes() {
+            let cells = GridPosition::all(width, height).collect::<Vec<_>>();
+
+            assert_eq!(cells.len(), (width * height) as usize);
+
+            for (packed, pos) in cells.iter().enumerate() {
+                assert_eq!(pos.packed, packed as i32);
+                assert_eq!(
+                    pos.unpacked(),
+                    IVec2::new(packed as i32 % width, packed as i32 / width)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn checked_new_rejects_outside_cells() {
+        assert!(GridPosition::checked_new(7, 3, IVec2::new(6, 2)).is_some());
+        assert!(GridPosition::checked_new(7, 3, IVec2::new(7, 0)).is_none());
+        assert!(GridPosition::checked_new(7, 3, IVec2::new(0, 3)).is_none());
+        assert!(GridPosition::checked_new(7, 3, IVec2::new(-1, 0)).is_none());
+    }
+
+    #[test]
+    fn neighbours_on_non_square_grids() {
+        let corner = GridPosition::new(7, 3, 6, 2);
+
+        let mut neighbours = corner
+            .neighbours_4()
+            .map(|pos| pos.unpacked())
+            .collect::<Vec<_>>();
+        neighbours.sort_by_key(|coords| (coords.y, coords.x));
+        assert_eq!(neighbours, [IVec2::new(6, 1), IVec2::new(5, 2)]);
+
+        assert_eq!(corner.neighbours_8().count(), 3);
+
+        let middle = GridPosition::new(3, 7, 1, 5);
+        assert_eq!(middle.neighbours_4().count(), 4);
+        assert_eq!(middle.neighbours_8().count(), 8);
+
+        for neighbour in middle.neighbours_8() {
+            assert_eq!(middle.chebyshev_distance(&neighbour), 1);
+        }
+    }
+
+    #[test]
+    fn offsets_on_non_square_grids() {
+        let pos = GridPosition::new(3, 7, 2, 6);
+
+        assert_eq!(pos.checked_offset(IVec2::X), None);
+        assert_eq!(
+            pos.checked_offset(IVec2::NEG_Y).map(|pos| pos.unpacked()),
+            Some(IVec2::new(2, 5))
+        );
+
+        assert_eq!(pos.wrapping_offset(IVec2::X).unpacked(), IVec2::new(0, 6));
+        assert_eq!(pos.wrapping_offset(IVec2::Y).unpacked(), IVec2::new(2, 0));
+        assert_eq!(
+            pos.wrapping_offset(IVec2::new(-4, 8)).unpacked(),
+            IVec2::new(1, 0)
+        );
+
+        assert_eq!(
+            pos.clamped_offset(IVec2::new(5, -10)).unpacked(),
+            IVec2::new(2, 0)
+        );
+        assert_eq!(
+            pos.clamped_offset(IVec2::new(-1, 1)).unpacked(),
+            IVec2::new(1, 6)
+        );
+    }
+
+    #[test]
+    fn rows_columns_and_rects() {
+        let row = GridPosition::row(7, 3, 1).map(|pos| pos.packed);
+        assert!(row.eq(7..14));
+
+        let column = GridPosition::column(7, 3, 2).map(|pos| pos.packed);
+        assert!(column.eq([2, 9, 16]));
+
+        assert_eq!(GridPosition::row(7, 3, 3).count(), 0);
+        assert_eq!(GridPosition::column(7, 3, 7).count(), 0);
+
+        let rect = GridPosition::rect(7, 3, IVec2::new(5, -1), IVec2::new(9, 1))
+            .map(|pos| pos.unpacked())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rect,
+            [
+                IVec2::new(5, 0),
+                IVec2::new(6, 0),
+                IVec2::new(5, 1),
+                IVec2::new(6, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn distances() {
+        let a = GridPosition::new(7, 3, 0, 0);
+        let b = GridPosition::new(7, 3, 6, 2);
+
+        assert_eq!(a.manhattan_distance(&b), 8);
+        assert_eq!(a.chebyshev_distance(&b), 6);
+        assert!((a.euclidean_distance(&b) - 40f64.sqrt()).abs() < 1e-12);
+        assert_eq!(DistanceMetric::Manhattan.max_distance(7, 3), 8.0);
     }
 }
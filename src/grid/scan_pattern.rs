@@ -0,0 +1,349 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::{mask::GridMask, position::GridPosition};
+
+/// Decides the order the scanline visits the cells of a grid in.
+pub trait ScanPattern: Send + Sync {
+    /// Every packed position of a `width` x `height` grid exactly once, in visiting order.
+    fn order(&self, width: i32, height: i32) -> Vec<i32>;
+}
+
+/// Left to right, top to bottom.
+pub struct RowMajor;
+
+impl ScanPattern for RowMajor {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        (0..width * height).collect()
+    }
+}
+
+/// Top to bottom, left to right.
+pub struct ColumnMajor;
+
+impl ScanPattern for ColumnMajor {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        (0..width)
+            .flat_map(|x| (0..height).map(move |y| GridPosition::pack(width, x, y)))
+            .collect()
+    }
+}
+
+/// Row by row, alternating direction every row (boustrophedon).
+pub struct Serpentine;
+
+impl ScanPattern for Serpentine {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        let mut order = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            if y % 2 == 0 {
+                order.extend((0..width).map(|x| GridPosition::pack(width, x, y)));
+            } else {
+                order.extend((0..width).rev().map(|x| GridPosition::pack(width, x, y)));
+            }
+        }
+
+        order
+    }
+}
+
+/// The odd rows (first, third, ...) followed by the even rows, like an interlaced display.
+pub struct Interlaced;
+
+impl ScanPattern for Interlaced {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        (0..height)
+            .step_by(2)
+            .chain((1..height).step_by(2))
+            .flat_map(|y| (0..width).map(move |x| GridPosition::pack(width, x, y)))
+            .collect()
+    }
+}
+
+/// Clockwise from the top left corner, spiralling inwards.
+pub struct Spiral;
+
+impl ScanPattern for Spiral {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        let mut order = Vec::with_capacity((width * height) as usize);
+
+        let (mut top, mut bottom, mut left, mut right) = (0, height - 1, 0, width - 1);
+
+        while top <= bottom && left <= right {
+            order.extend((left..=right).map(|x| GridPosition::pack(width, x, top)));
+            top += 1;
+
+            order.extend((top..=bottom).map(|y| GridPosition::pack(width, right, y)));
+            right -= 1;
+
+            if top <= bottom {
+                order.extend(
+                    (left..=right)
+                        .rev()
+                        .map(|x| GridPosition::pack(width, x, bottom)),
+                );
+                bottom -= 1;
+            }
+
+            if left <= right {
+                order.extend(
+                    (top..=bottom)
+                        .rev()
+                        .map(|y| GridPosition::pack(width, left, y)),
+                );
+                left += 1;
+            }
+        }
+
+        order
+    }
+}
+
+/// A Hilbert curve over the smallest power of two square covering the grid, skipping the cells
+/// that fall outside of it.
+pub struct Hilbert;
+
+impl Hilbert {
+    /// converts a distance along a hilbert curve of side `n` into coordinates
+    fn point(n: i32, dist: i32) -> (i32, i32) {
+        let (mut x, mut y) = (0, 0);
+        let mut t = dist;
+        let mut s = 1;
+
+        while s < n {
+            let rx = 1 & (t / 2);
+            let ry = 1 & (t ^ rx);
+
+            if ry == 0 {
+                if rx == 1 {
+                    x = s - 1 - x;
+                    y = s - 1 - y;
+                }
+
+                std::mem::swap(&mut x, &mut y);
+            }
+
+            x += s * rx;
+            y += s * ry;
+            t /= 4;
+            s *= 2;
+        }
+
+        (x, y)
+    }
+}
+
+impl ScanPattern for Hilbert {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        let side = (width.max(height).max(1) as u32).next_power_of_two() as i32;
+
+        (0..side * side)
+            .map(|dist| Self::point(side, dist))
+            .filter(|&(x, y)| x < width && y < height)
+            .map(|(x, y)| GridPosition::pack(width, x, y))
+            .collect()
+    }
+}
+
+/// A random permutation of the grid, identical for every run with the same seed.
+pub struct SeededRandom(pub u64);
+
+impl ScanPattern for SeededRandom {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        let mut order = RowMajor.order(width, height);
+
+        order.shuffle(&mut StdRng::seed_from_u64(self.0));
+
+        order
+    }
+}
+
+/// The built-in scan patterns, in a form that can be stored on a level.
+#[derive(Reflect, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScanPatternKind {
+    #[default]
+    RowMajor,
+    ColumnMajor,
+    Serpentine,
+    Interlaced,
+    Spiral,
+    Hilbert,
+    SeededRandom {
+        seed: u64,
+    },
+}
+
+impl ScanPattern for ScanPatternKind {
+    fn order(&self, width: i32, height: i32) -> Vec<i32> {
+        match *self {
+            Self::RowMajor => RowMajor.order(width, height),
+            Self::ColumnMajor => ColumnMajor.order(width, height),
+            Self::Serpentine => Serpentine.order(width, height),
+            Self::Interlaced => Interlaced.order(width, height),
+            Self::Spiral => Spiral.order(width, height),
+            Self::Hilbert => Hilbert.order(width, height),
+            Self::SeededRandom { seed } => SeededRandom(seed).order(width, height),
+        }
+    }
+}
+
+/// A scan pattern evaluated for a specific grid, with a reverse lookup from packed positions to
+/// their step in the scan.
+#[derive(Reflect, Clone, Debug, Default)]
+pub struct ScanOrder {
+    order: Vec<i32>,
+    steps: Vec<usize>,
+}
+
+impl ScanOrder {
+    pub fn new(pattern: &(impl ScanPattern + ?Sized), width: i32, height: i32) -> Self {
+        let order = pattern.order(width, height);
+
+        let mut steps = vec![usize::MAX; (width * height) as usize];
+
+        for (step, &packed) in order.iter().enumerate() {
+            steps[packed as usize] = step;
+        }
+
+        debug_assert!(
+            steps.iter().all(|&step| step != usize::MAX),
+            "scan pattern must visit every cell exactly once"
+        );
+
+        Self { order, steps }
+    }
+
+    /// Like [`ScanOrder::new`], but skips the cells missing from `mask` so the scan moves straight
+    /// past them. A missing cell maps to the step of the next cell that is scanned. Masks with no
+    /// cells on this grid leave the order unmasked.
+    pub fn masked(
+        pattern: &(impl ScanPattern + ?Sized),
+        width: i32,
+        height: i32,
+        mask: &GridMask,
+    ) -> Self {
+        let unmasked = Self::new(pattern, width, height);
+
+        let contains = |packed: i32| {
+            mask.contains(&GridPosition {
+                width,
+                height,
+                packed,
+            })
+        };
+
+        let order = unmasked
+            .order
+            .iter()
+            .copied()
+            .filter(|&packed| contains(packed))
+            .collect::<Vec<_>>();
+
+        if order.is_empty() {
+            return unmasked;
+        }
+
+        let mut steps = unmasked.steps;
+        let mut next = 0;
+
+        for &packed in &unmasked.order {
+            steps[packed as usize] = next % order.len();
+
+            if contains(packed) {
+                next += 1;
+            }
+        }
+
+        Self { order, steps }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// packed position visited at `step`
+    pub fn packed_at(&self, step: usize) -> i32 {
+        self.order[step % self.order.len()]
+    }
+
+    /// step at which `pos` is visited
+    pub fn step_of(&self, pos: &GridPosition) -> usize {
+        self.steps[pos.packed as usize]
+    }
+
+    /// progress through the scan in [0, 1) at which `pos` is visited
+    pub fn normalised(&self, pos: &GridPosition) -> f64 {
+        self.step_of(pos) as f64 / self.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERNS: [ScanPatternKind; 7] = [
+        ScanPatternKind::RowMajor,
+        ScanPatternKind::ColumnMajor,
+        ScanPatternKind::Serpentine,
+        ScanPatternKind::Interlaced,
+        ScanPatternKind::Spiral,
+        ScanPatternKind::Hilbert,
+        ScanPatternKind::SeededRandom { seed: 42 },
+    ];
+
+    const SIZES: [(i32, i32); 7] = [(1, 1), (1, 6), (6, 1), (3, 3), (5, 4), (4, 7), (21, 11)];
+
+    #[test]
+    fn visits_every_cell_exactly_once() {
+        for pattern in PATTERNS {
+            for (width, height) in SIZES {
+                let mut order = pattern.order(width, height);
+                order.sort_unstable();
+
+                assert_eq!(
+                    order,
+                    (0..width * height).collect::<Vec<_>>(),
+                    "{pattern:?} on {width}x{height}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn empty_grids_have_no_order() {
+        for pattern in PATTERNS {
+            assert!(pattern.order(0, 5).is_empty(), "{pattern:?}");
+            assert!(pattern.order(5, 0).is_empty(), "{pattern:?}");
+        }
+    }
+
+    #[test]
+    fn steps_match_order() {
+        for pattern in PATTERNS {
+            for (width, height) in SIZES {
+                let order = ScanOrder::new(&pattern, width, height);
+
+                for step in 0..order.len() {
+                    let pos = GridPosition {
+                        width,
+                        height,
+                        packed: order.packed_at(step),
+                    };
+
+                    assert_eq!(order.step_of(&pos), step, "{pattern:?} on {width}x{height}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_random_is_deterministic() {
+        assert_eq!(SeededRandom(7).order(5, 4), SeededRandom(7).order(5, 4));
+        assert_ne!(SeededRandom(7).order(5, 4), SeededRandom(8).order(5, 4));
+    }
+}
@@ -0,0 +1,542 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs,
+    path::Path,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Where the player's bindings are saved, relative to the working directory.
+pub const INPUT_MAP_PATH: &str = "input.ron";
+
+/// Something the player can do, bound to any number of inputs by the [`InputMap`]. Systems read
+/// these from [`ActionState`] rather than looking at keys and buttons themselves.
+#[derive(
+    Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    /// Where a bound mouse button clicks on the grid. Other bindings place it on the hovered
+    /// pixel, or a random one when nothing is hovered.
+    PlaceUserPixel,
+    Confirm,
+    Pause,
+    /// steps the game clock while paused
+    StepClock,
+    SlowDown,
+    SpeedUp,
+    ToggleInspector,
+    /// opens and closes the rebinding screen
+    Rebind,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::PlaceUserPixel,
+        Action::Confirm,
+        Action::Pause,
+        Action::StepClock,
+        Action::SlowDown,
+        Action::SpeedUp,
+        Action::ToggleInspector,
+        Action::Rebind,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::PlaceUserPixel => "Place user pixel",
+            Action::Confirm => "Confirm",
+            Action::Pause => "Pause",
+            Action::StepClock => "Step clock",
+            Action::SlowDown => "Slow down",
+            Action::SpeedUp => "Speed up",
+            Action::ToggleInspector => "Toggle inspector",
+            Action::Rebind => "Rebind controls",
+        }
+    }
+}
+
+/// A single input an [`Action`] can be bound to.
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// the button on any connected gamepad
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    fn pressed(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+        gamepads: &Query<&Gamepad>,
+    ) -> bool {
+        match *self {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse.pressed(button),
+            Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::Mouse(button) => write!(f, "Mouse {button:?}"),
+            Binding::Gamepad(button) => write!(f, "Gamepad {button:?}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InputMapError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for InputMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not access input bindings: {err}"),
+            Self::Ron(err) => write!(f, "could not parse input bindings: {err}"),
+            Self::Serialize(err) => write!(f, "could not write input bindings: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for InputMapError {}
+
+impl From<std::io::Error> for InputMapError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for InputMapError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Ron(err)
+    }
+}
+
+impl From<ron::Error> for InputMapError {
+    fn from(err: ron::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+/// The inputs bound to each [`Action`]. Saved to [`INPUT_MAP_PATH`] whenever it changes.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::{Gamepad as Pad, Key};
+
+        let bindings = [
+            (
+                Action::MoveUp,
+                vec![
+                    Key(KeyCode::ArrowUp),
+                    Key(KeyCode::KeyW),
+                    Pad(GamepadButton::DPadUp),
+                ],
+            ),
+            (
+                Action::MoveDown,
+                vec![
+                    Key(KeyCode::ArrowDown),
+                    Key(KeyCode::KeyS),
+                    Pad(GamepadButton::DPadDown),
+                ],
+            ),
+            (
+                Action::MoveLeft,
+                vec![
+                    Key(KeyCode::ArrowLeft),
+                    Key(KeyCode::KeyA),
+                    Pad(GamepadButton::DPadLeft),
+                ],
+            ),
+            (
+                Action::MoveRight,
+                vec![
+                    Key(KeyCode::ArrowRight),
+                    Key(KeyCode::KeyD),
+                    Pad(GamepadButton::DPadRight),
+                ],
+            ),
+            (
+                Action::PlaceUserPixel,
+                vec![
+                    Key(KeyCode::Space),
+                    Binding::Mouse(MouseButton::Left),
+                    Pad(GamepadButton::West),
+                ],
+            ),
+            (
+                Action::Confirm,
+                vec![Key(KeyCode::Enter), Pad(GamepadButton::South)],
+            ),
+            (
+                Action::Pause,
+                vec![Key(KeyCode::KeyP), Pad(GamepadButton::Start)],
+            ),
+            (
+                Action::StepClock,
+                vec![Key(KeyCode::Period), Pad(GamepadButton::North)],
+            ),
+            (
+                Action::SlowDown,
+                vec![Key(KeyCode::BracketLeft), Pad(GamepadButton::LeftTrigger)],
+            ),
+            (
+                Action::SpeedUp,
+                vec![Key(KeyCode::BracketRight), Pad(GamepadButton::RightTrigger)],
+            ),
+            // the inspector and the rebinding screen are both worked with the mouse, so a gamepad
+            // button to open them would only open something the gamepad can't use
+            (Action::ToggleInspector, vec![Key(KeyCode::Escape)]),
+            (Action::Rebind, vec![Key(KeyCode::F1)]),
+        ];
+
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `binding` to `action` as well as whatever it is already bound to.
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputMapError> {
+        Ok(ron::de::from_bytes(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), InputMapError> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+
+        Ok(fs::write(path, text)?)
+    }
+
+    /// The bindings saved at [`INPUT_MAP_PATH`], or the defaults if there aren't any.
+    pub fn load_or_default() -> Self {
+        match Self::load(INPUT_MAP_PATH) {
+            Ok(map) => map,
+            Err(InputMapError::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(err) => {
+                warn!("using the default input bindings: {err}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Which actions are held down this frame, worked out from the [`InputMap`] before `Update`.
+#[derive(Resource, Default, Debug)]
+pub struct ActionState {
+    /// every action whose inputs are held, blocked or not
+    held: HashSet<Action>,
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// whether the action started being held this frame
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    fn set(&mut self, held: HashSet<Action>, allowed: impl Fn(Action) -> bool) {
+        self.just_pressed = held
+            .difference(&self.held)
+            .copied()
+            .filter(|&action| allowed(action))
+            .collect();
+        self.pressed = held
+            .iter()
+            .copied()
+            .filter(|&action| allowed(action))
+            .collect();
+        self.held = held;
+    }
+}
+
+/// While this exists, only the actions in `except` reach the game, for example while the
+/// rebinding screen is taking input. An action held when it is unblocked doesn't count as just
+/// pressed.
+#[derive(Resource, Debug, Default)]
+pub struct ActionsBlocked {
+    pub except: Vec<Action>,
+}
+
+pub(super) fn update_action_state(
+    map: Res<InputMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    blocked: Option<Res<ActionsBlocked>>,
+    mut state: ResMut<ActionState>,
+) {
+    let held = Action::ALL
+        .into_iter()
+        .filter(|&action| {
+            map.bindings(action)
+                .iter()
+                .any(|binding| binding.pressed(&keys, &mouse, &gamepads))
+        })
+        .collect();
+
+    state.set(held, |action| {
+        blocked
+            .as_ref()
+            .is_none_or(|blocked| blocked.except.contains(&action))
+    });
+}
+
+/// The [`InputMap`] as it was last loaded or saved.
+pub(super) struct SavedInputMap(InputMap);
+
+impl FromWorld for SavedInputMap {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource::<InputMap>().clone())
+    }
+}
+
+impl SavedInputMap {
+    /// Saves `map` to `path` if it differs from the saved one, returning whether it did.
+    fn save(&mut self, map: &InputMap, path: impl AsRef<Path>) -> Result<bool, InputMapError> {
+        if *map == self.0 {
+            return Ok(false);
+        }
+
+        map.save(path)?;
+        self.0 = map.clone();
+
+        Ok(true)
+    }
+}
+
+/// Saves the map once it differs from the saved one, so starting the game or rebinding an action
+/// to what it already was doesn't rewrite the file.
+pub(super) fn save_input_map(map: Res<InputMap>, mut saved: Local<SavedInputMap>) {
+    if let Err(err) = saved.save(&map, INPUT_MAP_PATH) {
+        warn!("{err}");
+    }
+}
+
+/// A run condition that turns on and off each time `action` is pressed, like
+/// `input_toggle_active` for keys.
+pub fn action_toggle_active(
+    default: bool,
+    action: Action,
+) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    let mut active = default;
+
+    move |actions: Res<ActionState>| {
+        active ^= actions.just_pressed(action);
+        active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// a file in the system's temp directory for `test` to save to, removed if it's already there
+    fn temp_path(test: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("scanlined-input-{}-{test}.ron", std::process::id()));
+
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn held(actions: &[Action]) -> HashSet<Action> {
+        actions.iter().copied().collect()
+    }
+
+    #[test]
+    fn default_map_round_trips_through_ron() {
+        let map = InputMap::default();
+
+        let text = ron::ser::to_string_pretty(&map, ron::ser::PrettyConfig::default()).unwrap();
+        let loaded: InputMap = ron::de::from_str(&text).unwrap();
+
+        assert_eq!(loaded, map);
+    }
+
+    #[test]
+    fn every_action_has_a_default_binding() {
+        let map = InputMap::default();
+
+        for action in Action::ALL {
+            assert!(!map.bindings(action).is_empty(), "{action:?}");
+        }
+    }
+
+    #[test]
+    fn gamepads_reach_everything_but_the_mouse_screens() {
+        let map = InputMap::default();
+        let mut buttons = HashSet::new();
+
+        for action in Action::ALL {
+            let pad = map
+                .bindings(action)
+                .iter()
+                .filter_map(|binding| match binding {
+                    Binding::Gamepad(button) => Some(*button),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            let mouse_screen = matches!(action, Action::ToggleInspector | Action::Rebind);
+
+            assert_eq!(pad.is_empty(), mouse_screen, "{action:?}");
+            assert!(
+                pad.iter().all(|&button| buttons.insert(button)),
+                "{action:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn saved_maps_load_back() {
+        let path = temp_path("saved_maps_load_back");
+
+        let mut map = InputMap::default();
+        map.clear(Action::Pause);
+        map.bind(Action::Rebind, Binding::Gamepad(GamepadButton::Select));
+
+        map.save(&path).unwrap();
+        let loaded = InputMap::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded, map);
+        assert!(loaded.bindings(Action::Pause).is_empty());
+    }
+
+    #[test]
+    fn loading_reports_what_went_wrong() {
+        let path = temp_path("loading_reports_what_went_wrong");
+
+        assert!(matches!(InputMap::load(&path), Err(InputMapError::Io(_))));
+
+        fs::write(&path, "not an input map").unwrap();
+        let result = InputMap::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert!(matches!(result, Err(InputMapError::Ron(_))));
+    }
+
+    #[test]
+    fn binding_twice_keeps_one() {
+        let mut map = InputMap::default();
+        let bindings = map.bindings(Action::Confirm).to_vec();
+
+        map.bind(Action::Confirm, bindings[0]);
+        assert_eq!(map.bindings(Action::Confirm), bindings);
+
+        map.clear(Action::Confirm);
+        map.bind(Action::Confirm, Binding::Key(KeyCode::KeyE));
+        map.bind(Action::Confirm, Binding::Key(KeyCode::KeyE));
+
+        assert_eq!(map.bindings(Action::Confirm), [Binding::Key(KeyCode::KeyE)]);
+    }
+
+    #[test]
+    fn unchanged_maps_are_not_saved() {
+        let path = temp_path("unchanged_maps_are_not_saved");
+        let mut saved = SavedInputMap(InputMap::default());
+
+        assert!(!saved.save(&InputMap::default(), &path).unwrap());
+        assert!(!path.exists());
+
+        let mut map = InputMap::default();
+        map.bind(Action::Pause, Binding::Key(KeyCode::Tab));
+
+        assert!(saved.save(&map, &path).unwrap());
+        assert_eq!(InputMap::load(&path).unwrap(), map);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(!saved.save(&map, &path).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn just_pressed_lasts_one_frame() {
+        let mut state = ActionState::default();
+
+        state.set(held(&[Action::Confirm]), |_| true);
+        assert!(state.pressed(Action::Confirm));
+        assert!(state.just_pressed(Action::Confirm));
+
+        state.set(held(&[Action::Confirm]), |_| true);
+        assert!(state.pressed(Action::Confirm));
+        assert!(!state.just_pressed(Action::Confirm));
+
+        state.set(held(&[]), |_| true);
+        assert!(!state.pressed(Action::Confirm));
+        assert!(!state.just_pressed(Action::Confirm));
+    }
+
+    #[test]
+    fn blocked_actions_are_not_pressed() {
+        let mut state = ActionState::default();
+        let only_rebind = |action| action == Action::Rebind;
+
+        state.set(held(&[Action::Confirm, Action::Rebind]), only_rebind);
+        assert!(!state.pressed(Action::Confirm));
+        assert!(!state.just_pressed(Action::Confirm));
+        assert!(state.just_pressed(Action::Rebind));
+
+        // still held when unblocked, so it was pressed while blocked and isn't just pressed now
+        state.set(held(&[Action::Confirm]), |_| true);
+        assert!(state.pressed(Action::Confirm));
+        assert!(!state.just_pressed(Action::Confirm));
+
+        state.set(held(&[]), |_| true);
+        state.set(held(&[Action::Confirm]), |_| true);
+        assert!(state.just_pressed(Action::Confirm));
+    }
+}
@@ -0,0 +1,287 @@
+pub mod actions;
+pub mod movement;
+pub mod picking;
+pub mod rebind;
+
+use actions::{save_input_map, update_action_state, Action, ActionState, Binding, InputMap};
+use bevy::{input::InputSystem, prelude::*};
+use movement::{move_user_pixel, slide_outlines, UserPixelMovement};
+use picking::{
+    click_hovered_pixel, update_hovered_pixel, HoveredPixel, PixelClicked, PixelHovered,
+};
+use rebind::{
+    capture_binding, rebind_buttons, toggle_rebind_screen, update_bindings_text, Rebinding,
+};
+
+use crate::{
+    grid::{config::GridConfig, mask::GridMask},
+    pixels::{components::UserPixelMarker, user::UserPixelCommands},
+    scenes::{
+        clock::{set_clock_speed, StepClock},
+        PauseState, SceneState,
+    },
+    utils::misc::random_grid_position,
+};
+
+/// Places a user pixel where a mouse button bound to [`Action::PlaceUserPixel`] clicks on the
+/// grid. Clicks that miss the grid don't place anything. Other bindings place it on the hovered
+/// pixel, or on a random one when nothing is hovered.
+#[allow(clippy::too_many_arguments)]
+fn place_user_pixel(
+    actions: Res<ActionState>,
+    map: Res<InputMap>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut clicks: EventReader<PixelClicked>,
+    mut commands: Commands,
+    hovered: Res<HoveredPixel>,
+    config: Res<GridConfig>,
+    mask: Option<Res<GridMask>>,
+) {
+    let bindings = map.bindings(Action::PlaceUserPixel);
+    let bound = |button: MouseButton| bindings.contains(&Binding::Mouse(button));
+
+    // blocked, for example by the rebinding screen
+    if !actions.pressed(Action::PlaceUserPixel) {
+        clicks.clear();
+        return;
+    }
+
+    for click in clicks.read().filter(|click| bound(click.button)) {
+        commands.add_user_pixel(click.pos, UserPixelMarker::default());
+    }
+
+    let clicked = mouse.get_just_pressed().any(|&button| bound(button));
+
+    if clicked || !actions.just_pressed(Action::PlaceUserPixel) {
+        return;
+    }
+
+    let Some(pos) = hovered
+        .0
+        .or_else(|| random_grid_position(config.width, config.height, mask.as_deref()))
+    else {
+        return;
+    };
+
+    commands.add_user_pixel(pos, UserPixelMarker::default());
+}
+
+/// Pauses and resumes the game, steps it while paused, and halves and doubles the game speed.
+fn clock_input(
+    actions: Res<ActionState>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    mut steps: EventWriter<StepClock>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        next_state.set(match state.get() {
+            PauseState::Running => PauseState::Paused,
+            PauseState::Paused => PauseState::Running,
+        });
+    }
+
+    if actions.just_pressed(Action::StepClock) && *state.get() == PauseState::Paused {
+        steps.send(StepClock);
+    }
+
+    if actions.just_pressed(Action::SlowDown) {
+        let speed = time.relative_speed_f64() / 2.0;
+        set_clock_speed(&mut time, speed);
+    }
+
+    if actions.just_pressed(Action::SpeedUp) {
+        let speed = time.relative_speed_f64() * 2.0;
+        set_clock_speed(&mut time, speed);
+    }
+}
+
+/// Starts the game from the main menu.
+fn confirm_input(actions: Res<ActionState>, mut next_scene: ResMut<NextState<SceneState>>) {
+    if actions.just_pressed(Action::Confirm) {
+        next_scene.set(SceneState::Game);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.insert_resource(InputMap::load_or_default());
+    app.init_resource::<ActionState>();
+    app.init_resource::<HoveredPixel>();
+    app.register_type::<UserPixelMovement>();
+    app.init_resource::<UserPixelMovement>();
+    app.add_event::<PixelHovered>();
+    app.add_event::<PixelClicked>();
+
+    app.add_systems(PreUpdate, update_action_state.after(InputSystem));
+    app.add_systems(
+        Update,
+        (
+            toggle_rebind_screen,
+            (
+                capture_binding,
+                rebind_buttons,
+                update_bindings_text
+                    .run_if(resource_changed::<InputMap>.or(resource_changed::<Rebinding>)),
+            )
+                .chain()
+                .run_if(resource_exists::<Rebinding>),
+            save_input_map.run_if(resource_changed::<InputMap>),
+        )
+            .chain(),
+    );
+    app.add_systems(Update, confirm_input.run_if(in_state(SceneState::MainMenu)));
+    app.add_systems(
+        Update,
+        (
+            update_hovered_pixel,
+            click_hovered_pixel,
+            place_user_pixel,
+            move_user_pixel,
+            slide_outlines,
+            clock_input,
+        )
+            .chain()
+            .run_if(in_state(SceneState::Game)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use actions::ActionsBlocked;
+
+    use crate::{
+        grid::position::GridPosition,
+        pixels::{components::Pixel, index::PixelGrid},
+    };
+
+    use super::*;
+
+    const CONFIG: GridConfig = GridConfig {
+        width: 4,
+        height: 3,
+        pixel_size: 10.0,
+        gap: 2.0,
+    };
+
+    const CLICK: Binding = Binding::Mouse(MouseButton::Left);
+    const SPACE: Binding = Binding::Key(KeyCode::Space);
+
+    fn app() -> App {
+        let mut app = App::new();
+
+        app.insert_resource(CONFIG);
+        app.insert_resource(InputMap::default());
+        app.init_resource::<ActionState>();
+        app.init_resource::<HoveredPixel>();
+        app.init_resource::<PixelGrid>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<ButtonInput<MouseButton>>();
+        app.add_event::<PixelClicked>();
+        app.add_systems(
+            Update,
+            (update_action_state, click_hovered_pixel, place_user_pixel).chain(),
+        );
+
+        for pos in GridPosition::all(CONFIG.width, CONFIG.height) {
+            app.world_mut().spawn(Pixel::new(pos));
+        }
+
+        app
+    }
+
+    /// holds `input` down for one frame while `hovered` is under the cursor
+    fn press(app: &mut App, hovered: Option<GridPosition>, input: Binding) {
+        let world = app.world_mut();
+        world.resource_mut::<HoveredPixel>().0 = hovered;
+
+        match input {
+            Binding::Key(key) => world.resource_mut::<ButtonInput<KeyCode>>().press(key),
+            Binding::Mouse(button) => world
+                .resource_mut::<ButtonInput<MouseButton>>()
+                .press(button),
+            Binding::Gamepad(_) => unreachable!(),
+        }
+
+        app.update();
+
+        let world = app.world_mut();
+        world.resource_mut::<ButtonInput<KeyCode>>().reset_all();
+        world.resource_mut::<ButtonInput<MouseButton>>().reset_all();
+    }
+
+    fn user_pixels(app: &mut App) -> Vec<GridPosition> {
+        let world = app.world_mut();
+
+        let mut positions = world
+            .query_filtered::<&Pixel, With<UserPixelMarker>>()
+            .iter(world)
+            .map(|pixel| pixel.pos())
+            .collect::<Vec<_>>();
+
+        positions.sort();
+        positions
+    }
+
+    #[test]
+    fn click_places_on_the_clicked_pixel() {
+        let mut app = app();
+        let pos = CONFIG.position(2, 1);
+
+        press(&mut app, Some(pos), CLICK);
+
+        assert_eq!(user_pixels(&mut app), [pos]);
+    }
+
+    #[test]
+    fn click_off_the_grid_places_nothing() {
+        let mut app = app();
+
+        // over a gap, outside of the grid, or over the inspector
+        press(&mut app, None, CLICK);
+
+        assert_eq!(user_pixels(&mut app), []);
+    }
+
+    #[test]
+    fn unbound_click_places_nothing() {
+        let mut app = app();
+
+        press(
+            &mut app,
+            Some(CONFIG.position(1, 1)),
+            Binding::Mouse(MouseButton::Right),
+        );
+
+        assert_eq!(user_pixels(&mut app), []);
+    }
+
+    #[test]
+    fn key_places_on_the_hovered_pixel() {
+        let mut app = app();
+        let pos = CONFIG.position(3, 2);
+
+        press(&mut app, Some(pos), SPACE);
+
+        assert_eq!(user_pixels(&mut app), [pos]);
+    }
+
+    #[test]
+    fn key_places_at_random_when_nothing_is_hovered() {
+        let mut app = app();
+
+        press(&mut app, None, SPACE);
+
+        assert_eq!(user_pixels(&mut app).len(), 1);
+    }
+
+    #[test]
+    fn blocked_click_places_nothing() {
+        let mut app = app();
+        app.init_resource::<ActionsBlocked>();
+
+        press(&mut app, Some(CONFIG.position(0, 0)), CLICK);
+        press(&mut app, None, SPACE);
+
+        assert_eq!(user_pixels(&mut app), []);
+    }
+}
@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+use super::actions::{Action, ActionState};
+use crate::{
+    easings::{standard::StandardEasing, Easing},
+    grid::position::GridPosition,
+    materials::pixel_instances::OutlineOffset,
+    pixels::{
+        components::Pixel,
+        index::PixelGrid,
+        user::{UserPixelCommands, UserPixels},
+    },
+};
+
+/// What happens when the user pixel is moved off the edge of the grid.
+#[derive(Reflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridEdges {
+    /// stops at the edge
+    #[default]
+    Clamp,
+    /// comes back in on the opposite edge
+    Wrap,
+}
+
+/// How the movement actions move the newest user pixel.
+#[derive(Reflect, Resource, Debug, Clone)]
+#[reflect(Resource)]
+pub struct UserPixelMovement {
+    pub edges: GridEdges,
+    /// seconds a key has to be held before it starts repeating
+    pub repeat_delay: f32,
+    /// moves per second while a key is repeating
+    pub repeat_rate: f32,
+    /// Seconds the outline takes to slide over to the new cell, or `None` (or no time at all) for
+    /// it to jump straight there.
+    pub slide: Option<f32>,
+}
+
+impl Default for UserPixelMovement {
+    fn default() -> Self {
+        Self {
+            edges: GridEdges::default(),
+            repeat_delay: 0.3,
+            repeat_rate: 12.0,
+            slide: Some(0.08),
+        }
+    }
+}
+
+/// The direction held down, and when it next moves the user pixel.
+#[derive(Default)]
+pub(super) struct KeyRepeat {
+    direction: IVec2,
+    /// seconds until the next move
+    countdown: f32,
+}
+
+/// An outline sliding from `start` back to its pixel, through `OutlineOffset`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct OutlineSlide {
+    start: Vec2,
+    elapsed: f32,
+    duration: f32,
+}
+
+/// Grid direction of the movement actions held down. Up is towards lower rows, since rows are
+/// laid out top to bottom.
+fn held_direction(actions: &ActionState) -> IVec2 {
+    let pressed = |action| actions.pressed(action) as i32;
+
+    IVec2::new(
+        pressed(Action::MoveRight) - pressed(Action::MoveLeft),
+        pressed(Action::MoveDown) - pressed(Action::MoveUp),
+    )
+}
+
+/// The cell `direction` from `pos`, skipping over cells the mask left out. `None` if there is
+/// nowhere to go.
+fn step_position(
+    pos: GridPosition,
+    direction: IVec2,
+    edges: GridEdges,
+    pixels: &PixelGrid,
+) -> Option<GridPosition> {
+    let mut next = pos;
+
+    for _ in 0..pos.width.max(pos.height) {
+        next = match edges {
+            GridEdges::Clamp => next.checked_offset(direction)?,
+            GridEdges::Wrap => next.wrapping_offset(direction),
+        };
+
+        if next == pos {
+            return None;
+        }
+
+        if pixels.get(&next).is_some() {
+            return Some(next);
+        }
+    }
+
+    None
+}
+
+/// Moves the newest user pixel one cell in the direction held, straight away and then repeating
+/// after `UserPixelMovement::repeat_delay`. Runs on real time, so it works while paused.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn move_user_pixel(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    actions: Res<ActionState>,
+    settings: Res<UserPixelMovement>,
+    mut repeat: Local<KeyRepeat>,
+    user_pixels: Res<UserPixels>,
+    pixel_grid: Res<PixelGrid>,
+    pixels: Query<(&Pixel, &Transform, Option<&OutlineOffset>)>,
+) {
+    let direction = held_direction(&actions);
+
+    if direction == IVec2::ZERO {
+        *repeat = KeyRepeat::default();
+        return;
+    }
+
+    if direction != repeat.direction {
+        // a new direction moves straight away
+        repeat.direction = direction;
+        repeat.countdown = settings.repeat_delay;
+    } else {
+        repeat.countdown -= time.delta_secs();
+
+        if repeat.countdown > 0.0 {
+            return;
+        }
+
+        repeat.countdown += 1.0 / settings.repeat_rate.max(f32::EPSILON);
+    }
+
+    let Some((pixel, from_transform, offset)) = user_pixels
+        .newest()
+        .and_then(|entity| pixels.get(entity).ok())
+    else {
+        return;
+    };
+
+    let Some(to) = step_position(pixel.pos(), direction, settings.edges, &pixel_grid) else {
+        return;
+    };
+
+    commands.move_user_pixel(pixel.pos(), to);
+
+    let slide = settings.slide.filter(|&duration| duration > 0.0);
+
+    let (Some(duration), Some(to_entity)) = (slide, pixel_grid.get(&to)) else {
+        return;
+    };
+
+    let Ok((_, to_transform, _)) = pixels.get(to_entity) else {
+        return;
+    };
+
+    // starting from wherever the outline is drawn now, so repeated moves slide smoothly
+    let start = from_transform.translation.truncate()
+        + offset.map_or(Vec2::ZERO, |offset| **offset)
+        - to_transform.translation.truncate();
+
+    commands.entity(to_entity).insert((
+        OutlineOffset(start),
+        OutlineSlide {
+            start,
+            elapsed: 0.0,
+            duration,
+        },
+    ));
+}
+
+/// Eases sliding outlines back onto their pixel, snapping onto it once the slide is over.
+pub(super) fn slide_outlines(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut slides: Query<(Entity, &mut OutlineSlide, &mut OutlineOffset)>,
+) {
+    for (entity, mut slide, mut offset) in &mut slides {
+        slide.elapsed += time.delta_secs();
+
+        let t = if slide.duration > 0.0 {
+            slide.elapsed / slide.duration
+        } else {
+            1.0
+        };
+
+        if t >= 1.0 {
+            commands
+                .entity(entity)
+                .remove::<(OutlineSlide, OutlineOffset)>();
+            continue;
+        }
+
+        **offset = slide.start * (1.0 - StandardEasing::CubicOut.evaluate(t as f64) as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// runs one frame of `slide_outlines` on an outline slid in from `start` over `duration`
+    fn slide_frame(duration: f32, delta: Duration) -> Option<Vec2> {
+        let mut app = App::new();
+        app.init_resource::<Time<Real>>();
+        app.add_systems(Update, slide_outlines);
+
+        let start = Vec2::new(10.0, 0.0);
+        let entity = app
+            .world_mut()
+            .spawn((
+                OutlineOffset(start),
+                OutlineSlide {
+                    start,
+                    elapsed: 0.0,
+                    duration,
+                },
+            ))
+            .id();
+
+        let mut time = app.world_mut().resource_mut::<Time<Real>>();
+        let startup = time.startup();
+
+        // the first update only sets the clock going
+        time.update_with_instant(startup);
+        time.update_with_instant(startup + delta);
+
+        app.update();
+
+        app.world()
+            .get::<OutlineOffset>(entity)
+            .map(|offset| **offset)
+    }
+
+    #[test]
+    fn outlines_slide_back_to_their_pixel() {
+        let offset = slide_frame(0.1, Duration::from_millis(50)).unwrap();
+
+        assert!(offset.x > 0.0 && offset.x < 10.0, "{offset}");
+        assert!(offset.is_finite());
+
+        assert_eq!(slide_frame(0.1, Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn empty_slides_snap_straight_back() {
+        assert_eq!(slide_frame(0.0, Duration::ZERO), None);
+        assert_eq!(slide_frame(-1.0, Duration::from_millis(10)), None);
+    }
+}
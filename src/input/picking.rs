@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use bevy_window::PrimaryWindow;
+
+use crate::{
+    camera::OrthoCamera2d,
+    grid::{config::GridConfig, mask::GridMask, position::GridPosition},
+    pixels::components::Pixel,
+};
+
+/// The pixel currently under the cursor, if any.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct HoveredPixel(pub Option<GridPosition>);
+
+/// Sent whenever the cursor moves onto a different pixel, or off of the grid.
+#[derive(Event, Debug)]
+pub struct PixelHovered {
+    pub previous: Option<GridPosition>,
+    pub current: Option<GridPosition>,
+}
+
+/// Sent when a mouse button is pressed over a pixel.
+#[derive(Event, Debug)]
+pub struct PixelClicked {
+    pub pos: GridPosition,
+    pub button: MouseButton,
+}
+
+/// While this exists the cursor doesn't pick any pixel, for example while it is over the
+/// inspector.
+#[derive(Resource, Debug, Default)]
+pub struct PickingBlocked;
+
+/// The cell under the cursor of `window`, as seen through `camera`. Cells missing from `mask`
+/// can't be picked.
+pub fn cursor_grid_position(
+    window: &Window,
+    (camera, camera_transform): (&Camera, &GlobalTransform),
+    config: &GridConfig,
+    mask: Option<&GridMask>,
+) -> Option<GridPosition> {
+    let cursor = window.cursor_position()?;
+    let world = camera.viewport_to_world_2d(camera_transform, cursor).ok()?;
+
+    Pixel::grid_position_at(world, window, config)
+        .filter(|pos| mask.is_none_or(|mask| mask.contains(pos)))
+}
+
+pub(super) fn update_hovered_pixel(
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<OrthoCamera2d>>,
+    config: Res<GridConfig>,
+    mask: Option<Res<GridMask>>,
+    blocked: Option<Res<PickingBlocked>>,
+    mut hovered: ResMut<HoveredPixel>,
+    mut events: EventWriter<PixelHovered>,
+) {
+    let current = match blocked {
+        Some(_) => None,
+        None => cursor_grid_position(&window, *camera, &config, mask.as_deref()),
+    };
+
+    if hovered.0 != current {
+        events.send(PixelHovered {
+            previous: hovered.0,
+            current,
+        });
+
+        hovered.0 = current;
+    }
+}
+
+pub(super) fn click_hovered_pixel(
+    buttons: Res<ButtonInput<MouseButton>>,
+    hovered: Res<HoveredPixel>,
+    mut events: EventWriter<PixelClicked>,
+) {
+    let Some(pos) = hovered.0 else {
+        return;
+    };
+
+    for &button in buttons.get_just_pressed() {
+        events.send(PixelClicked { pos, button });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        render::camera::{camera_system, ManualTextureViews},
+        window::{WindowCreated, WindowResized, WindowScaleFactorChanged},
+    };
+
+    use super::*;
+
+    /// A window and a camera set up like the game's, zoomed out by `scale`, with the camera's
+    /// projection already worked out.
+    fn app(scale: f32) -> App {
+        let mut app = App::new();
+
+        app.init_resource::<Assets<Image>>();
+        app.init_resource::<ManualTextureViews>();
+        app.add_event::<WindowCreated>();
+        app.add_event::<WindowResized>();
+        app.add_event::<WindowScaleFactorChanged>();
+        app.add_event::<AssetEvent<Image>>();
+        app.add_systems(Update, camera_system::<OrthographicProjection>);
+
+        let world = app.world_mut();
+
+        world.spawn((
+            Window {
+                resolution: (1200., 900.).into(),
+                ..default()
+            },
+            PrimaryWindow,
+        ));
+        world.spawn((
+            OrthoCamera2d,
+            OrthographicProjection {
+                viewport_origin: Vec2::new(0.0, 1.0),
+                scale,
+                ..OrthographicProjection::default_2d()
+            },
+        ));
+
+        app.update();
+        app
+    }
+
+    /// Moves the cursor over `point` and picks whatever is under it.
+    fn pick(
+        app: &mut App,
+        point: Vec2,
+        config: &GridConfig,
+        mask: Option<&GridMask>,
+    ) -> Option<GridPosition> {
+        let world = app.world_mut();
+
+        let (camera, camera_transform) = world.query::<(&Camera, &GlobalTransform)>().single(world);
+        let (camera, camera_transform) = (camera.clone(), *camera_transform);
+
+        let cursor = camera
+            .world_to_viewport(&camera_transform, point.extend(0.0))
+            .unwrap();
+
+        let mut window = world.query::<&mut Window>().single_mut(world);
+        window.set_cursor_position(Some(cursor));
+
+        cursor_grid_position(&window, (&camera, &camera_transform), config, mask)
+    }
+
+    #[test]
+    fn round_trips_at_any_camera_scale() {
+        let config = GridConfig {
+            width: 7,
+            height: 5,
+            ..default()
+        };
+
+        for scale in [1.0, 1.5, 2.0, 4.0] {
+            let mut app = app(scale);
+            let window = Window {
+                resolution: (1200., 900.).into(),
+                ..default()
+            };
+
+            for pos in GridPosition::all(config.width, config.height) {
+                let center = Pixel::new(pos).get_translation(&window, &config).truncate();
+                let edge = Vec2::splat(config.pixel_size / 2.0 - 0.5);
+
+                assert_eq!(pick(&mut app, center, &config, None), Some(pos), "x{scale}");
+                assert_eq!(
+                    pick(&mut app, center + edge, &config, None),
+                    Some(pos),
+                    "x{scale}"
+                );
+                assert_eq!(
+                    pick(&mut app, center - edge, &config, None),
+                    Some(pos),
+                    "x{scale}"
+                );
+
+                let gap = Vec2::new(config.stride() / 2.0, 0.0);
+                assert_eq!(
+                    pick(&mut app, center + gap, &config, None),
+                    None,
+                    "x{scale}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_picked_off_the_grid_or_the_mask() {
+        let config = GridConfig {
+            width: 3,
+            height: 3,
+            ..default()
+        };
+        let window = Window {
+            resolution: (1200., 900.).into(),
+            ..default()
+        };
+        let mask = GridMask::from_text("###\n#.#\n###").unwrap();
+        let mut app = app(1.0);
+
+        let center = Pixel::new(config.center())
+            .get_translation(&window, &config)
+            .truncate();
+        assert_eq!(pick(&mut app, center, &config, None), Some(config.center()));
+        assert_eq!(pick(&mut app, center, &config, Some(&mask)), None);
+
+        let past_edge = Vec2::new(2.0 * config.stride(), 0.0);
+        assert_eq!(pick(&mut app, center + past_edge, &config, None), None);
+        assert_eq!(pick(&mut app, center - past_edge, &config, None), None);
+
+        // off the window entirely
+        let world = app.world_mut();
+        let (camera, camera_transform) = world.query::<(&Camera, &GlobalTransform)>().single(world);
+        let (camera, camera_transform) = (camera.clone(), *camera_transform);
+
+        let mut window = world.query::<&mut Window>().single_mut(world);
+        window.set_cursor_position(None);
+
+        assert_eq!(
+            cursor_grid_position(&window, (&camera, &camera_transform), &config, None),
+            None
+        );
+    }
+
+    #[test]
+    fn blocked_picking_hovers_nothing() {
+        let config = GridConfig {
+            width: 3,
+            height: 3,
+            ..default()
+        };
+        let window = Window {
+            resolution: (1200., 900.).into(),
+            ..default()
+        };
+
+        let mut app = app(1.0);
+        app.insert_resource(config);
+        app.init_resource::<HoveredPixel>();
+        app.add_event::<PixelHovered>();
+        app.add_systems(Update, update_hovered_pixel);
+
+        let center = Pixel::new(config.center())
+            .get_translation(&window, &config)
+            .truncate();
+        pick(&mut app, center, &config, None);
+
+        app.update();
+        assert_eq!(
+            app.world().resource::<HoveredPixel>().0,
+            Some(config.center())
+        );
+
+        app.insert_resource(PickingBlocked);
+        app.update();
+        assert_eq!(app.world().resource::<HoveredPixel>().0, None);
+
+        let hovered = app
+            .world()
+            .resource::<Events<PixelHovered>>()
+            .iter_current_update_events()
+            .map(|event| (event.previous, event.current))
+            .collect::<Vec<_>>();
+        assert_eq!(hovered, [(Some(config.center()), None)]);
+
+        app.world_mut().remove_resource::<PickingBlocked>();
+        app.update();
+        assert_eq!(
+            app.world().resource::<HoveredPixel>().0,
+            Some(config.center())
+        );
+    }
+}
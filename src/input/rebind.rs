@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+
+use super::actions::{Action, ActionState, ActionsBlocked, Binding, InputMap};
+
+const SCREEN_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+const BUTTON_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const BUTTON_HOVERED_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+const FONT_SIZE: f32 = 16.0;
+
+/// Root of the rebinding screen, opened and closed with [`Action::Rebind`].
+#[derive(Component)]
+pub struct RebindScreen;
+
+/// Exists while the rebinding screen is open.
+#[derive(Resource, Default, Debug)]
+pub struct Rebinding {
+    /// the action the next input pressed is bound to
+    pub listening: Option<Action>,
+}
+
+/// A button on the rebinding screen.
+#[derive(Component, Clone, Copy)]
+pub enum RebindButton {
+    /// waits for an input to bind to the action
+    Add(Action),
+    /// unbinds the action from everything
+    Clear(Action),
+    Defaults,
+    Close,
+}
+
+/// Lists what an action is bound to.
+#[derive(Component)]
+pub struct BindingsText(Action);
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, button: RebindButton) {
+    parent
+        .spawn((
+            Button,
+            button,
+            Node {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOR),
+        ))
+        .with_child((Text::new(label), TextFont::from_font_size(FONT_SIZE)));
+}
+
+fn spawn_rebind_screen(commands: &mut Commands) {
+    commands
+        .spawn((
+            RebindScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(SCREEN_COLOR),
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.0),
+                    padding: UiRect::all(Val::Px(16.0)),
+                    ..default()
+                })
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new("Controls"),
+                        TextFont::from_font_size(FONT_SIZE * 1.5),
+                    ));
+
+                    for action in Action::ALL {
+                        panel
+                            .spawn(Node {
+                                column_gap: Val::Px(12.0),
+                                align_items: AlignItems::Center,
+                                ..default()
+                            })
+                            .with_children(|row| {
+                                row.spawn((
+                                    Text::new(action.label()),
+                                    TextFont::from_font_size(FONT_SIZE),
+                                    Node {
+                                        width: Val::Px(160.0),
+                                        ..default()
+                                    },
+                                ));
+                                row.spawn((
+                                    BindingsText(action),
+                                    Text::default(),
+                                    TextFont::from_font_size(FONT_SIZE),
+                                    Node {
+                                        width: Val::Px(420.0),
+                                        ..default()
+                                    },
+                                ));
+
+                                spawn_button(row, "Add", RebindButton::Add(action));
+                                spawn_button(row, "Clear", RebindButton::Clear(action));
+                            });
+                    }
+
+                    panel
+                        .spawn(Node {
+                            column_gap: Val::Px(12.0),
+                            margin: UiRect::top(Val::Px(8.0)),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            spawn_button(row, "Reset to defaults", RebindButton::Defaults);
+                            spawn_button(row, "Close", RebindButton::Close);
+                        });
+                });
+        });
+
+    commands.insert_resource(Rebinding::default());
+    commands.insert_resource(ActionsBlocked {
+        except: vec![Action::Rebind],
+    });
+}
+
+fn close_rebind_screen(commands: &mut Commands, screen: Entity) {
+    commands.entity(screen).despawn_recursive();
+    commands.remove_resource::<Rebinding>();
+    commands.remove_resource::<ActionsBlocked>();
+}
+
+pub(super) fn toggle_rebind_screen(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    screen: Query<Entity, With<RebindScreen>>,
+) {
+    if !actions.just_pressed(Action::Rebind) {
+        return;
+    }
+
+    match screen.get_single() {
+        Ok(screen) => close_rebind_screen(&mut commands, screen),
+        Err(_) => spawn_rebind_screen(&mut commands),
+    }
+}
+
+/// Binds the first input pressed while the screen is listening for one. Runs before the buttons
+/// are handled, so the click that starts listening isn't bound itself.
+pub(super) fn capture_binding(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<Rebinding>,
+    mut blocked: ResMut<ActionsBlocked>,
+    mut map: ResMut<InputMap>,
+) {
+    let Some(action) = rebinding.listening else {
+        return;
+    };
+
+    let binding = keys
+        .get_just_pressed()
+        .next()
+        .map(|&key| Binding::Key(key))
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .next()
+                .map(|&button| Binding::Mouse(button))
+        })
+        .or_else(|| {
+            gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next())
+                .map(|&button| Binding::Gamepad(button))
+        });
+
+    let Some(binding) = binding else {
+        return;
+    };
+
+    map.bind(action, binding);
+
+    rebinding.listening = None;
+    blocked.except = vec![Action::Rebind];
+}
+
+pub(super) fn rebind_buttons(
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &RebindButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut blocked: ResMut<ActionsBlocked>,
+    mut map: ResMut<InputMap>,
+    screen: Query<Entity, With<RebindScreen>>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        *color = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVERED_COLOR,
+        }
+        .into();
+
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match *button {
+            RebindButton::Add(action) => {
+                rebinding.listening = Some(action);
+                // every input is up for grabs, including the one that closes the screen
+                blocked.except.clear();
+            }
+            RebindButton::Clear(action) => map.clear(action),
+            RebindButton::Defaults => *map = InputMap::default(),
+            RebindButton::Close => {
+                if let Ok(screen) = screen.get_single() {
+                    close_rebind_screen(&mut commands, screen);
+                }
+            }
+        }
+    }
+}
+
+pub(super) fn update_bindings_text(
+    map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    mut texts: Query<(&BindingsText, &mut Text)>,
+) {
+    for (BindingsText(action), mut text) in &mut texts {
+        **text = if rebinding.listening == Some(*action) {
+            "press a key or button...".to_string()
+        } else if map.bindings(*action).is_empty() {
+            "unbound".to_string()
+        } else {
+            map.bindings(*action)
+                .iter()
+                .map(Binding::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+    }
+}
@@ -1,12 +1,12 @@
 mod camera;
 #[cfg(debug_assertions)]
 mod debug;
-mod easings;
-mod grid;
-mod input;
+pub mod easings;
+pub mod grid;
+pub mod input;
 mod materials;
-mod pixels;
-mod scenes;
+pub mod pixels;
+pub mod scenes;
 mod utils;
 mod window;
 
@@ -27,6 +27,8 @@ impl Plugin for ScanlinedApp {
             window::plugin,
             camera::plugin,
             materials::plugin,
+            easings::plugin,
+            grid::plugin,
             pixels::plugin,
             scenes::plugin,
             input::plugin,
@@ -1,15 +1,8 @@
-pub mod rect_outlined;
+pub mod pixel_instances;
 
-use bevy::{
-    app::App,
-    render::{mesh::MeshVertexAttribute, render_resource::VertexFormat},
-    sprite::Material2dPlugin,
-};
-use rect_outlined::OutlinedRectMaterial;
-
-pub const ATTRIBUTE_RECT_SIZE: MeshVertexAttribute =
-    MeshVertexAttribute::new("RectSize", 94583659670978, VertexFormat::Float32x2);
+use bevy::app::App;
+use pixel_instances::PixelInstancingPlugin;
 
 pub fn plugin(app: &mut App) {
-    app.add_plugins((Material2dPlugin::<OutlinedRectMaterial>::default(),));
+    app.add_plugins((PixelInstancingPlugin,));
 }
@@ -0,0 +1,678 @@
+use bevy::{
+    core_pipeline::core_2d::{Transparent2d, CORE_2D_DEPTH_FORMAT},
+    ecs::{
+        query::ROQueryItem,
+        system::{lifetimeless::SRes, SystemParamItem},
+    },
+    image::BevyDefault,
+    math::FloatOrd,
+    prelude::*,
+    render::{
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand,
+            RenderCommandResult, SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+        },
+        render_resource::{
+            binding_types::{storage_buffer_read_only_sized, uniform_buffer},
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, BlendState,
+            BufferUsages, ColorTargetState, ColorWrites, CompareFunction, DepthStencilState,
+            FragmentState, MultisampleState, PipelineCache, PrimitiveState, PrimitiveTopology,
+            RawBufferVec, RenderPipelineDescriptor, ShaderStages, SpecializedRenderPipeline,
+            SpecializedRenderPipelines, TextureFormat, UniformBuffer, VertexBufferLayout,
+            VertexFormat, VertexState, VertexStepMode,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        sync_world::MainEntity,
+        view::{ExtractedView, ViewTarget},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+    sprite::{Mesh2dPipeline, Mesh2dPipelineKey, SetMesh2dViewBindGroup},
+};
+
+use bytemuck::{Pod, Zeroable};
+use std::num::NonZeroU64;
+
+use crate::{
+    grid::config::GridConfig,
+    pixels::{
+        components::Pixel,
+        decay::{GpuPixelDecay, PixelDecay, PixelDecays},
+    },
+    scenes::clock::game_millis,
+};
+
+const SHADER_ASSET_PATH: &str = "shaders/pixel_instances.wgsl";
+
+/// How far apart the points the shader measures time from are, in milliseconds. Game clock
+/// milliseconds stop fitting in an `f32` to the millisecond after a few hours, so the clock and
+/// lit times are uploaded relative to the latest of these instead.
+pub const CLOCK_EPOCH_MILLIS: f64 = 60_000.0;
+
+/// the game clock millisecond the shader measures time from, at `millis`
+pub fn clock_epoch(millis: f64) -> f64 {
+    (millis / CLOCK_EPOCH_MILLIS).floor() * CLOCK_EPOCH_MILLIS
+}
+
+/// What the renderer draws for a pixel. Every pixel is drawn in a single instanced draw call from
+/// a buffer of these, rather than each having its own mesh and material.
+#[derive(Component, Reflect, Copy, Clone, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct PixelInstance {
+    pub color: LinearRgba,
+    /// Multiplies the alpha of `color`, when it's worked out on the CPU. Otherwise the shader
+    /// fades the pixel from `lit_time` with its `decay`.
+    pub brightness: Option<f32>,
+    pub outline_color: LinearRgba,
+    /// width of the outline drawn around the outside of the pixel, 0 for none
+    pub outline_thickness: f32,
+    /// game clock milliseconds when the pixel was last lit
+    pub lit_time: f64,
+    /// index of the pixel's decay in `PixelDecays`
+    pub decay: u32,
+}
+
+impl Default for PixelInstance {
+    fn default() -> Self {
+        Self {
+            color: LinearRgba::WHITE,
+            brightness: None,
+            outline_color: LinearRgba::WHITE,
+            outline_thickness: 0.0,
+            lit_time: 0.0,
+            decay: 0,
+        }
+    }
+}
+
+/// Draws the pixel's outline `offset` world units away from the pixel, on its own, for example
+/// while a user pixel slides between cells.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct OutlineOffset(pub Vec2);
+
+/// One pixel's entry in the instance buffer, laid out to match `Instance` in the shader.
+#[derive(Pod, Zeroable, Copy, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub struct PixelInstanceData {
+    /// translation of the pixel's center, with its size in `w`
+    pub translation_size: Vec4,
+    pub color: Vec4,
+    pub outline_color: Vec4,
+    /// lit time since `epoch`, outline thickness, brightness or -1 for the shader to fade the
+    /// pixel, and decay index
+    pub light: Vec4,
+}
+
+impl PixelInstanceData {
+    /// `epoch` is the [`clock_epoch`] the lit time is measured from.
+    pub fn new(translation: Vec3, size: f32, instance: &PixelInstance, epoch: f64) -> Self {
+        Self {
+            translation_size: translation.extend(size),
+            color: instance.color.to_vec4(),
+            outline_color: instance.outline_color.to_vec4(),
+            light: Vec4::new(
+                (instance.lit_time - epoch) as f32,
+                instance.outline_thickness,
+                instance.brightness.unwrap_or(-1.0),
+                instance.decay as f32,
+            ),
+        }
+    }
+}
+
+/// The pixels copied out of the main world this frame, in the render world.
+#[derive(Resource, Default, Debug, Deref)]
+pub struct ExtractedPixelInstances(Vec<PixelInstanceData>);
+
+impl ExtractedPixelInstances {
+    /// Replaces the extracted instances with `pixels`, with lit times measured from `epoch`.
+    /// Doesn't touch the GPU, so it works the same on a plain `World`.
+    pub fn extract<'a>(
+        &mut self,
+        pixel_size: f32,
+        epoch: f64,
+        pixels: impl IntoIterator<
+            Item = (
+                &'a GlobalTransform,
+                &'a PixelInstance,
+                Option<&'a OutlineOffset>,
+            ),
+        >,
+    ) {
+        self.0.clear();
+
+        for (transform, instance, offset) in pixels {
+            let translation = transform.translation();
+
+            let Some(offset) = offset.filter(|_| instance.outline_thickness > 0.0) else {
+                self.0.push(PixelInstanceData::new(
+                    translation,
+                    pixel_size,
+                    instance,
+                    epoch,
+                ));
+                continue;
+            };
+
+            self.0.push(PixelInstanceData::new(
+                translation,
+                pixel_size,
+                &PixelInstance {
+                    outline_thickness: 0.0,
+                    ..*instance
+                },
+                epoch,
+            ));
+
+            // the outline on its own, around a transparent pixel
+            self.0.push(PixelInstanceData::new(
+                translation + offset.extend(0.0),
+                pixel_size,
+                &PixelInstance {
+                    color: LinearRgba::NONE,
+                    ..*instance
+                },
+                epoch,
+            ));
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn extract_pixel_instances(
+    mut extracted: ResMut<ExtractedPixelInstances>,
+    time: Extract<Res<Time<Virtual>>>,
+    config: Extract<Option<Res<GridConfig>>>,
+    pixels: Extract<Query<(&GlobalTransform, &PixelInstance, Option<&OutlineOffset>), With<Pixel>>>,
+) {
+    let pixel_size = config.as_ref().map_or(0.0, |config| config.pixel_size);
+
+    extracted.extract(pixel_size, clock_epoch(game_millis(&time)), &pixels);
+}
+
+/// The game clock and every decay in `PixelDecays`, in the render world.
+#[derive(Resource, Default, Debug)]
+struct ExtractedPixelDecays {
+    /// game clock milliseconds since the current [`clock_epoch`]
+    clock: f32,
+    decays: Vec<GpuPixelDecay>,
+    changed: bool,
+}
+
+fn extract_pixel_decays(
+    mut extracted: ResMut<ExtractedPixelDecays>,
+    time: Extract<Res<Time<Virtual>>>,
+    decays: Extract<Option<Res<PixelDecays>>>,
+) {
+    let millis = game_millis(&time);
+
+    extracted.clock = (millis - clock_epoch(millis)) as f32;
+    extracted.changed = false;
+
+    if let Some(decays) = decays.as_ref().filter(|decays| decays.is_changed()) {
+        extracted.decays.clear();
+        extracted.decays.extend(decays.baked());
+        extracted.changed = true;
+    }
+
+    // the storage buffer can't be empty
+    if extracted.decays.is_empty() {
+        extracted
+            .decays
+            .push(GpuPixelDecay::bake(&PixelDecay::default()));
+        extracted.changed = true;
+    }
+}
+
+#[derive(Resource)]
+struct PixelInstanceBuffer {
+    instances: RawBufferVec<PixelInstanceData>,
+}
+
+impl Default for PixelInstanceBuffer {
+    fn default() -> Self {
+        let mut instances = RawBufferVec::new(BufferUsages::VERTEX);
+        instances.set_label(Some("pixel_instance_buffer"));
+
+        Self { instances }
+    }
+}
+
+fn prepare_pixel_instance_buffer(
+    extracted: Res<ExtractedPixelInstances>,
+    mut buffer: ResMut<PixelInstanceBuffer>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    buffer.instances.clear();
+
+    for instance in extracted.iter() {
+        buffer.instances.push(*instance);
+    }
+
+    buffer.instances.write_buffer(&render_device, &render_queue);
+}
+
+#[derive(Resource)]
+struct PixelDecayBuffers {
+    /// game clock milliseconds since the current [`clock_epoch`] in `x`
+    clock: UniformBuffer<Vec4>,
+    decays: RawBufferVec<GpuPixelDecay>,
+    bind_group: Option<BindGroup>,
+}
+
+impl Default for PixelDecayBuffers {
+    fn default() -> Self {
+        let mut clock = UniformBuffer::default();
+        clock.set_label(Some("pixel_clock_buffer"));
+
+        let mut decays = RawBufferVec::new(BufferUsages::STORAGE);
+        decays.set_label(Some("pixel_decay_buffer"));
+
+        Self {
+            clock,
+            decays,
+            bind_group: None,
+        }
+    }
+}
+
+fn prepare_pixel_decay_buffers(
+    extracted: Res<ExtractedPixelDecays>,
+    mut buffers: ResMut<PixelDecayBuffers>,
+    pipeline: Res<PixelInstancePipeline>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    let buffers = buffers.as_mut();
+
+    buffers.clock.set(Vec4::new(extracted.clock, 0.0, 0.0, 0.0));
+    buffers.clock.write_buffer(&render_device, &render_queue);
+
+    if extracted.changed {
+        buffers.decays.clear();
+
+        for decay in &extracted.decays {
+            buffers.decays.push(*decay);
+        }
+
+        buffers.decays.write_buffer(&render_device, &render_queue);
+    }
+
+    let (Some(clock), Some(decays)) = (buffers.clock.binding(), buffers.decays.binding()) else {
+        return;
+    };
+
+    // the buffers can be reallocated by either write, so the bind group is made fresh each frame
+    buffers.bind_group = Some(render_device.create_bind_group(
+        "pixel_decay_bind_group",
+        &pipeline.decay_layout,
+        &BindGroupEntries::sequential((clock, decays)),
+    ));
+}
+
+#[derive(Resource)]
+struct PixelInstancePipeline {
+    view_layout: BindGroupLayout,
+    /// the game clock and the decay table
+    decay_layout: BindGroupLayout,
+    shader: Handle<Shader>,
+}
+
+impl FromWorld for PixelInstancePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let decay_layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "pixel_decay_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::VERTEX,
+                (
+                    uniform_buffer::<Vec4>(false),
+                    storage_buffer_read_only_sized(
+                        false,
+                        NonZeroU64::new(size_of::<GpuPixelDecay>() as u64),
+                    ),
+                ),
+            ),
+        );
+
+        Self {
+            view_layout: world.resource::<Mesh2dPipeline>().view_layout.clone(),
+            decay_layout,
+            shader: world.resource::<AssetServer>().load(SHADER_ASSET_PATH),
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for PixelInstancePipeline {
+    type Key = Mesh2dPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let format = if key.contains(Mesh2dPipelineKey::HDR) {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+
+        RenderPipelineDescriptor {
+            label: Some("pixel_instance_pipeline".into()),
+            layout: vec![self.view_layout.clone(), self.decay_layout.clone()],
+            push_constant_ranges: vec![],
+            vertex: VertexState {
+                shader: self.shader.clone(),
+                shader_defs: vec![],
+                entry_point: "vertex".into(),
+                // the quad itself comes from the vertex index, so the only buffer is the instances
+                buffers: vec![VertexBufferLayout::from_vertex_formats(
+                    VertexStepMode::Instance,
+                    [VertexFormat::Float32x4; 4],
+                )],
+            },
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs: vec![],
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                ..default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: CORE_2D_DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: default(),
+                bias: default(),
+            }),
+            multisample: MultisampleState {
+                count: key.msaa_samples(),
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+struct SetPixelDecayBindGroup<const I: usize>;
+
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetPixelDecayBindGroup<I> {
+    type Param = SRes<PixelDecayBuffers>;
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        _item: &P,
+        _view: ROQueryItem<'w, Self::ViewQuery>,
+        _entity: Option<ROQueryItem<'w, Self::ItemQuery>>,
+        buffers: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(bind_group) = &buffers.into_inner().bind_group else {
+            return RenderCommandResult::Skip;
+        };
+
+        pass.set_bind_group(I, bind_group, &[]);
+
+        RenderCommandResult::Success
+    }
+}
+
+struct DrawPixelInstanceBuffer;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawPixelInstanceBuffer {
+    type Param = SRes<PixelInstanceBuffer>;
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        _item: &P,
+        _view: ROQueryItem<'w, Self::ViewQuery>,
+        _entity: Option<ROQueryItem<'w, Self::ItemQuery>>,
+        buffer: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let instances = &buffer.into_inner().instances;
+
+        let Some(instance_buffer) = instances.buffer() else {
+            return RenderCommandResult::Skip;
+        };
+
+        pass.set_vertex_buffer(0, instance_buffer.slice(..));
+        pass.draw(0..4, 0..instances.len() as u32);
+
+        RenderCommandResult::Success
+    }
+}
+
+type DrawPixelInstances = (
+    SetItemPipeline,
+    SetMesh2dViewBindGroup<0>,
+    SetPixelDecayBindGroup<1>,
+    DrawPixelInstanceBuffer,
+);
+
+fn queue_pixel_instances(
+    extracted: Res<ExtractedPixelInstances>,
+    draw_functions: Res<DrawFunctions<Transparent2d>>,
+    pipeline: Res<PixelInstancePipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<PixelInstancePipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    mut transparent_phases: ResMut<ViewSortedRenderPhases<Transparent2d>>,
+    views: Query<(Entity, &ExtractedView, &Msaa)>,
+) {
+    if extracted.is_empty() {
+        return;
+    }
+
+    let draw_function = draw_functions.read().id::<DrawPixelInstances>();
+
+    for (view_entity, view, msaa) in &views {
+        let Some(transparent_phase) = transparent_phases.get_mut(&view_entity) else {
+            continue;
+        };
+
+        let key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples())
+            | Mesh2dPipelineKey::from_hdr(view.hdr);
+
+        // the draw doesn't read anything from the item's entity, every pixel is in the buffer
+        transparent_phase.add(Transparent2d {
+            sort_key: FloatOrd(0.0),
+            entity: (Entity::PLACEHOLDER, MainEntity::from(Entity::PLACEHOLDER)),
+            pipeline: pipelines.specialize(&pipeline_cache, &pipeline, key),
+            draw_function,
+            batch_range: 0..1,
+            extra_index: PhaseItemExtraIndex::NONE,
+        });
+    }
+}
+
+/// Draws every [`PixelInstance`] with one shared quad and a per-instance vertex buffer.
+pub struct PixelInstancingPlugin;
+
+impl Plugin for PixelInstancingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PixelInstance>();
+        app.register_type::<OutlineOffset>();
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<ExtractedPixelInstances>()
+            .init_resource::<PixelInstanceBuffer>()
+            .init_resource::<ExtractedPixelDecays>()
+            .init_resource::<PixelDecayBuffers>()
+            .init_resource::<SpecializedRenderPipelines<PixelInstancePipeline>>()
+            .add_render_command::<Transparent2d, DrawPixelInstances>()
+            .add_systems(
+                ExtractSchedule,
+                (extract_pixel_instances, extract_pixel_decays),
+            )
+            .add_systems(
+                Render,
+                (
+                    (prepare_pixel_instance_buffer, prepare_pixel_decay_buffers)
+                        .in_set(RenderSet::PrepareResources),
+                    queue_pixel_instances.in_set(RenderSet::Queue),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<PixelInstancePipeline>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(world: &mut World, pixel_size: f32, epoch: f64) -> ExtractedPixelInstances {
+        let mut extracted = ExtractedPixelInstances::default();
+
+        let mut pixels =
+            world.query::<(&GlobalTransform, &PixelInstance, Option<&OutlineOffset>)>();
+        extracted.extract(pixel_size, epoch, pixels.iter(world));
+
+        extracted
+    }
+
+    #[test]
+    fn packs_instances() {
+        let mut world = World::new();
+
+        let instance = PixelInstance {
+            color: LinearRgba::new(0.1, 0.2, 0.3, 0.4),
+            brightness: None,
+            outline_color: LinearRgba::new(0.5, 0.6, 0.7, 0.8),
+            outline_thickness: 2.0,
+            lit_time: 1250.0,
+            decay: 3,
+        };
+
+        world.spawn((
+            GlobalTransform::from_translation(Vec3::new(10.0, -20.0, 1.0)),
+            instance,
+        ));
+
+        let extracted = extract(&mut world, 56.0, 0.0);
+
+        assert_eq!(
+            **extracted,
+            [PixelInstanceData {
+                translation_size: Vec4::new(10.0, -20.0, 1.0, 56.0),
+                color: Vec4::new(0.1, 0.2, 0.3, 0.4),
+                outline_color: Vec4::new(0.5, 0.6, 0.7, 0.8),
+                light: Vec4::new(1250.0, 2.0, -1.0, 3.0),
+            }]
+        );
+    }
+
+    #[test]
+    fn packs_cpu_brightness() {
+        let mut world = World::new();
+
+        world.spawn((
+            GlobalTransform::default(),
+            PixelInstance {
+                brightness: Some(0.25),
+                ..default()
+            },
+        ));
+
+        let extracted = extract(&mut world, 8.0, 0.0);
+
+        assert_eq!(extracted[0].light.z, 0.25);
+        assert_eq!(extracted[0].translation_size.w, 8.0);
+    }
+
+    #[test]
+    fn offset_outlines_are_drawn_on_their_own() {
+        let mut world = World::new();
+
+        let instance = PixelInstance {
+            color: LinearRgba::RED,
+            outline_thickness: 2.0,
+            ..default()
+        };
+
+        world.spawn((
+            GlobalTransform::from_translation(Vec3::new(1.0, 2.0, 3.0)),
+            instance,
+            OutlineOffset(Vec2::new(4.0, -5.0)),
+        ));
+
+        let extracted = extract(&mut world, 10.0, 0.0);
+
+        assert_eq!(extracted.len(), 2);
+
+        // the pixel without its outline
+        assert_eq!(
+            extracted[0].translation_size,
+            Vec4::new(1.0, 2.0, 3.0, 10.0)
+        );
+        assert_eq!(extracted[0].color, LinearRgba::RED.to_vec4());
+        assert_eq!(extracted[0].light.y, 0.0);
+
+        // the outline around a transparent pixel, moved by the offset
+        assert_eq!(
+            extracted[1].translation_size,
+            Vec4::new(5.0, -3.0, 3.0, 10.0)
+        );
+        assert_eq!(extracted[1].color, Vec4::ZERO);
+        assert_eq!(extracted[1].light.y, 2.0);
+    }
+
+    #[test]
+    fn offsets_without_an_outline_are_ignored() {
+        let mut world = World::new();
+
+        world.spawn((
+            GlobalTransform::default(),
+            PixelInstance::default(),
+            OutlineOffset(Vec2::ONE),
+        ));
+
+        let extracted = extract(&mut world, 10.0, 0.0);
+
+        assert_eq!(extracted.len(), 1);
+        assert_eq!(
+            extracted[0].translation_size,
+            Vec4::new(0.0, 0.0, 0.0, 10.0)
+        );
+    }
+
+    #[test]
+    fn lit_times_are_relative_to_the_epoch() {
+        // about ten hours in, where absolute milliseconds are 4 apart as `f32`s
+        let millis = 36_000_123.5;
+        let epoch = clock_epoch(millis);
+
+        assert_eq!(epoch, 36_000_000.0);
+        assert_eq!(clock_epoch(59_999.0), 0.0);
+
+        let mut world = World::new();
+
+        world.spawn((
+            GlobalTransform::default(),
+            PixelInstance {
+                lit_time: millis - 0.25,
+                ..default()
+            },
+        ));
+
+        let extracted = extract(&mut world, 10.0, epoch);
+        let clock = (millis - epoch) as f32;
+
+        assert_eq!(extracted[0].light.x, 123.25);
+        assert_eq!(clock - extracted[0].light.x, 0.25);
+    }
+}
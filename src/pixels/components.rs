@@ -1,65 +1,216 @@
 use bevy::prelude::*;
 use bevy::{
-    ecs::component::Component, math::Vec3, reflect::Reflect, render::mesh::Mesh2d,
-    transform::components::Transform,
+    ecs::component::Component, math::Vec3, reflect::Reflect, transform::components::Transform,
 };
-use bevy_mod_index::index::IndexInfo;
-use bevy_mod_index::prelude::IndexRefreshPolicy;
-use bevy_mod_index::storage::HashmapStorage;
 use bevy_window::Window;
 
-use crate::grid::position::GridPosition;
+use crate::{
+    easings::expr::BellShape,
+    grid::{config::GridConfig, position::GridPosition},
+    materials::pixel_instances::PixelInstance,
+};
 
-use super::{PIXEL_GAP, PIXEL_SIZE};
+use super::{
+    index::{pixel_inserted_hook, pixel_replaced_hook},
+    state::PixelState,
+};
 
+/// A cell of the pixel grid. Its entity is tracked in [`super::index::PixelGrid`], which is why
+/// `pos` can't be changed in place: move a pixel by inserting a new `Pixel` instead.
 #[derive(Component, Reflect)]
-#[require(Transform, Mesh2d, PixelLifetime, PixelColor)]
+#[require(
+    Transform,
+    PixelInstance,
+    PixelLifetime,
+    PixelLights,
+    PixelColor,
+    PixelState
+)]
+#[component(on_insert = pixel_inserted_hook, on_replace = pixel_replaced_hook)]
 pub struct Pixel {
-    pub pos: GridPosition,
+    pos: GridPosition,
 }
 
 impl Pixel {
-    pub fn get_translation(&self, window: &Window) -> Vec3 {
+    pub fn new(pos: GridPosition) -> Self {
+        Self { pos }
+    }
+
+    pub fn pos(&self) -> GridPosition {
+        self.pos
+    }
+
+    pub fn get_translation(&self, window: &Window, config: &GridConfig) -> Vec3 {
         let wres = &window.resolution;
 
         let grid_coords = self.pos.unpacked();
 
-        let pos_x = ((grid_coords.x as f32 - self.pos.width as f32 / 2.0)
-            * (PIXEL_SIZE + PIXEL_GAP))
+        let pos_x = ((grid_coords.x as f32 - self.pos.width as f32 / 2.0) * config.stride())
             + (wres.width() / 2.0);
 
-        let pos_y = -((grid_coords.y as f32 - self.pos.height as f32 / 2.0)
-            * (PIXEL_SIZE + PIXEL_GAP))
+        let pos_y = -((grid_coords.y as f32 - self.pos.height as f32 / 2.0) * config.stride())
             - (wres.height() / 2.0);
 
         Vec3::new(pos_x, pos_y, 1.0)
     }
-}
 
-#[derive(Reflect)]
-pub struct AllPixels;
+    /// The inverse of [`Pixel::get_translation`]: the cell covering `world`, or `None` if it falls
+    /// in a gap between pixels or outside of the grid.
+    pub fn grid_position_at(
+        world: Vec2,
+        window: &Window,
+        config: &GridConfig,
+    ) -> Option<GridPosition> {
+        let wres = &window.resolution;
+
+        let grid_x = ((world.x - wres.width() / 2.0) / config.stride()) + config.width as f32 / 2.0;
+        let grid_y =
+            -((world.y + wres.height() / 2.0) / config.stride()) + config.height as f32 / 2.0;
 
-impl IndexInfo for AllPixels {
-    type Component = Pixel;
-    type Value = GridPosition;
+        let cell = Vec2::new(grid_x, grid_y).round();
 
-    type Storage = HashmapStorage<Self>;
+        // distance from the center of the cell, in world units
+        let offset = (Vec2::new(grid_x, grid_y) - cell).abs() * config.stride();
 
-    const REFRESH_POLICY: IndexRefreshPolicy = IndexRefreshPolicy::WhenUsed;
+        if offset.max_element() > config.pixel_size / 2.0 {
+            return None;
+        }
 
-    fn value(c: &Self::Component) -> Self::Value {
-        c.pos
+        GridPosition::checked_new(config.width, config.height, cell.as_ivec2())
     }
 }
 
 #[derive(Component, Reflect, Default, Debug, PartialEq, PartialOrd, Eq, Ord, Copy, Clone)]
 pub struct PixelMarker;
 
-#[derive(Component, Reflect, Default, Debug, PartialEq, PartialOrd, Eq, Ord, Copy, Clone)]
-pub struct UserPixelMarker;
+/// Marks a pixel placed by the player. The scanline slows down around it following a bell curve,
+/// either `bell` or the one from the level's easing asset.
+#[derive(Component, Reflect, Default, Debug, PartialEq, Copy, Clone)]
+pub struct UserPixelMarker {
+    pub bell: Option<BellShape>,
+}
 
 #[derive(Component, Default, Deref, DerefMut)]
 pub struct PixelLifetime(pub f64);
 
+/// When each cursor last lit the pixel, indexed by `ScanCursor::index`. Cursors that haven't lit
+/// it yet are at negative infinity.
+#[derive(Component, Default, Debug, Deref, DerefMut)]
+pub struct PixelLights(pub Vec<f64>);
+
 #[derive(Component, Default, Deref)]
 pub struct PixelColor(pub u32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the default layout, one with a wide gap and one with no gap at all
+    const CONFIGS: [GridConfig; 3] = [
+        GridConfig {
+            width: 21,
+            height: 11,
+            pixel_size: 56.0,
+            gap: 5.0,
+        },
+        GridConfig {
+            width: 4,
+            height: 7,
+            pixel_size: 10.0,
+            gap: 30.0,
+        },
+        GridConfig {
+            width: 9,
+            height: 2,
+            pixel_size: 33.0,
+            gap: 0.0,
+        },
+    ];
+
+    fn center(pos: GridPosition, window: &Window, config: &GridConfig) -> Vec2 {
+        Pixel::new(pos).get_translation(window, config).truncate()
+    }
+
+    #[test]
+    fn round_trips_with_translation() {
+        let window = Window::default();
+
+        for config in CONFIGS {
+            // just inside each edge of the pixel, as well as its center
+            let inside = config.pixel_size / 2.0 - 0.01;
+
+            for pos in GridPosition::all(config.width, config.height) {
+                let center = center(pos, &window, &config);
+
+                for offset in [
+                    Vec2::ZERO,
+                    Vec2::new(inside, 0.0),
+                    Vec2::new(-inside, 0.0),
+                    Vec2::new(0.0, inside),
+                    Vec2::new(inside, -inside),
+                ] {
+                    assert_eq!(
+                        Pixel::grid_position_at(center + offset, &window, &config),
+                        Some(pos),
+                        "{pos:?} + {offset}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gaps_pick_nothing() {
+        let window = Window::default();
+
+        for config in CONFIGS.into_iter().filter(|config| config.gap > 0.0) {
+            let pos = config.position(1, 1);
+            let center = center(pos, &window, &config);
+            let between = config.stride() / 2.0;
+            let gap_edge = config.pixel_size / 2.0 + 0.01;
+
+            for offset in [
+                Vec2::new(between, 0.0),
+                Vec2::new(0.0, -between),
+                Vec2::new(-gap_edge, 0.0),
+                Vec2::new(gap_edge, gap_edge),
+            ] {
+                assert_eq!(
+                    Pixel::grid_position_at(center + offset, &window, &config),
+                    None,
+                    "{offset}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn outside_the_grid_picks_nothing() {
+        let window = Window::default();
+
+        for config in CONFIGS {
+            let stride = config.stride();
+            let first = center(config.position(0, 0), &window, &config);
+            let last = center(
+                config.position(config.width - 1, config.height - 1),
+                &window,
+                &config,
+            );
+
+            // one cell past each edge, where a pixel would be if the grid were bigger
+            for point in [
+                first - Vec2::new(stride, 0.0),
+                first + Vec2::new(0.0, stride),
+                last + Vec2::new(stride, 0.0),
+                last - Vec2::new(0.0, stride),
+                Vec2::new(-1e6, 1e6),
+            ] {
+                assert_eq!(
+                    Pixel::grid_position_at(point, &window, &config),
+                    None,
+                    "{point}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    hash::{Hash, Hasher},
+    mem,
+};
+
+use bevy::{prelude::*, utils::HashMap};
+use bytemuck::{Pod, Zeroable};
+
+use crate::easings::{expr::EasingExpr, standard::StandardEasing, Easing};
+
+/// How a pixel fades out after it is lit. Pixels without one use the level's decay.
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Component)]
+pub struct PixelDecay {
+    /// milliseconds from being lit until the pixel is fully faded
+    pub duration: f64,
+    /// Brightness over the fade, evaluated on the normalised time since the pixel was lit. Should
+    /// go from 1 to 0.
+    #[reflect(ignore)]
+    pub curve: EasingExpr,
+    /// brightness the pixel fades down to instead of going dark
+    pub floor: f64,
+    /// How far through the fade red, green and blue are relative to the pixel as a whole, on top
+    /// of its brightness. A rate of 0 keeps the channel's colour and higher rates fade it out
+    /// sooner, shifting the pixel's colour as it fades.
+    pub channel_rates: Option<Vec3>,
+}
+
+impl Default for PixelDecay {
+    fn default() -> Self {
+        Self {
+            duration: 6000.0,
+            // (1 - t)^3
+            curve: EasingExpr::Invert(Box::new(EasingExpr::Standard(StandardEasing::CubicOut))),
+            floor: 0.0,
+            channel_rates: None,
+        }
+    }
+}
+
+/// Hashes the numbers and the kind of curve, leaving the rest of the curve to `PartialEq`. A
+/// decay with a NaN in it never equals itself, so it just takes a new slot in [`PixelDecays`].
+impl Hash for PixelDecay {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // adding zero turns -0 into 0, which it is equal to
+        let bits = |value: f64| (value + 0.0).to_bits();
+
+        bits(self.duration).hash(state);
+        bits(self.floor).hash(state);
+        mem::discriminant(&self.curve).hash(state);
+
+        if let Some(rates) = self.channel_rates {
+            for rate in rates.to_array() {
+                bits(rate as f64).hash(state);
+            }
+        }
+    }
+}
+
+impl Eq for PixelDecay {}
+
+impl PixelDecay {
+    fn fade(&self, progress: f64) -> f64 {
+        self.curve
+            .evaluate(progress.clamp(0.0, 1.0))
+            .clamp(0.0, 1.0)
+    }
+
+    /// normalised time through the fade `elapsed` milliseconds after the pixel was lit
+    fn progress(&self, elapsed: f64) -> f64 {
+        if self.duration > 0.0 {
+            elapsed / self.duration
+        } else {
+            1.0
+        }
+    }
+
+    /// brightness `elapsed` milliseconds after the pixel was lit
+    pub fn brightness(&self, elapsed: f64) -> f64 {
+        self.floor + (1.0 - self.floor) * self.fade(self.progress(elapsed))
+    }
+
+    /// what the pixel's colour is multiplied by `elapsed` milliseconds after it was lit
+    pub fn channel_factors(&self, elapsed: f64) -> LinearRgba {
+        let Some(rates) = self.channel_rates else {
+            return LinearRgba::WHITE;
+        };
+
+        let progress = self.progress(elapsed);
+        let factor = |rate: f32| self.fade(progress * rate as f64) as f32;
+
+        LinearRgba::rgb(factor(rates.x), factor(rates.y), factor(rates.z))
+    }
+}
+
+/// number of points the decay curve is sampled at for the shader
+pub const DECAY_SAMPLES: usize = 64;
+
+/// A [`PixelDecay`] baked into the form the pixel shader reads, laid out to match `PixelDecay` in
+/// `pixel_instances.wgsl`. Its methods do the same maths as the shader, in the same order, so they
+/// act as its CPU reference.
+#[derive(Pod, Zeroable, Copy, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub struct GpuPixelDecay {
+    /// duration, floor, and 1 if `channel_rates` are used
+    pub params: [f32; 4],
+    pub channel_rates: [f32; 4],
+    /// the curve sampled evenly over [0, 1]
+    pub samples: [f32; DECAY_SAMPLES],
+}
+
+impl GpuPixelDecay {
+    pub fn bake(decay: &PixelDecay) -> Self {
+        let mut samples = [0.0; DECAY_SAMPLES];
+
+        for (i, sample) in samples.iter_mut().enumerate() {
+            *sample = decay.fade(i as f64 / (DECAY_SAMPLES - 1) as f64) as f32;
+        }
+
+        let rates = decay.channel_rates.unwrap_or(Vec3::ZERO);
+
+        Self {
+            params: [
+                decay.duration as f32,
+                decay.floor as f32,
+                if decay.channel_rates.is_some() {
+                    1.0
+                } else {
+                    0.0
+                },
+                0.0,
+            ],
+            channel_rates: [rates.x, rates.y, rates.z, 0.0],
+            samples,
+        }
+    }
+
+    fn fade(&self, progress: f32) -> f32 {
+        let scaled = progress.clamp(0.0, 1.0) * (DECAY_SAMPLES - 1) as f32;
+        let i = (scaled.floor() as usize).min(DECAY_SAMPLES - 2);
+        let t = scaled - i as f32;
+
+        let (a, b) = (self.samples[i], self.samples[i + 1]);
+
+        a + (b - a) * t
+    }
+
+    fn progress(&self, elapsed: f32) -> f32 {
+        let duration = self.params[0];
+
+        if duration > 0.0 {
+            elapsed / duration
+        } else {
+            1.0
+        }
+    }
+
+    /// brightness `elapsed` milliseconds after the pixel was lit
+    pub fn brightness(&self, elapsed: f32) -> f32 {
+        let floor = self.params[1];
+
+        floor + (1.0 - floor) * self.fade(self.progress(elapsed))
+    }
+
+    /// what the pixel's red, green and blue are multiplied by `elapsed` milliseconds after it was
+    /// lit
+    pub fn channel_factors(&self, elapsed: f32) -> Vec3 {
+        if self.params[2] == 0.0 {
+            return Vec3::ONE;
+        }
+
+        let progress = self.progress(elapsed);
+        let rates = self.channel_rates;
+
+        Vec3::new(
+            self.fade(progress * rates[0]),
+            self.fade(progress * rates[1]),
+            self.fade(progress * rates[2]),
+        )
+    }
+}
+
+/// Most decays [`PixelDecays`] holds: as many as fit in the smallest storage buffer binding every
+/// backend has to support.
+pub const MAX_PIXEL_DECAYS: usize = (128 << 20) / mem::size_of::<GpuPixelDecay>();
+
+/// Every distinct decay the pixels use, so the shader can look them up by index. The first one is
+/// the one it was [reset](Self::reset) with, which any decay past [`MAX_PIXEL_DECAYS`] falls back
+/// to.
+#[derive(Resource, Default, Debug)]
+pub struct PixelDecays {
+    decays: Vec<PixelDecay>,
+    indices: HashMap<PixelDecay, u32>,
+}
+
+impl PixelDecays {
+    pub fn position(&self, decay: &PixelDecay) -> Option<u32> {
+        self.indices.get(decay).copied()
+    }
+
+    pub fn push(&mut self, decay: PixelDecay) -> u32 {
+        if self.decays.len() >= MAX_PIXEL_DECAYS {
+            warn_once!("more than {MAX_PIXEL_DECAYS} pixel decays, using the first for the rest");
+            return 0;
+        }
+
+        let index = self.decays.len() as u32;
+
+        self.indices.insert(decay.clone(), index);
+        self.decays.push(decay);
+
+        index
+    }
+
+    /// Forgets every decay but `first`, so the ones nothing uses any more are dropped.
+    pub fn reset(&mut self, first: &PixelDecay) {
+        self.decays.clear();
+        self.indices.clear();
+        self.push(first.clone());
+    }
+
+    pub fn len(&self) -> usize {
+        self.decays.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decays.is_empty()
+    }
+
+    pub fn baked(&self) -> impl Iterator<Item = GpuPixelDecay> + '_ {
+        self.decays.iter().map(GpuPixelDecay::bake)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::easings::expr::EasingExpr;
+
+    use super::*;
+
+    /// largest difference between a 64 sample linear table and the default curve
+    const TOLERANCE: f32 = 1e-3;
+
+    fn decays() -> Vec<PixelDecay> {
+        vec![
+            PixelDecay::default(),
+            PixelDecay {
+                duration: 800.0,
+                curve: EasingExpr::Linear.invert(),
+                floor: 0.2,
+                channel_rates: Some(Vec3::new(0.0, 1.0, 2.5)),
+            },
+            PixelDecay {
+                duration: 0.0,
+                floor: 0.1,
+                channel_rates: Some(Vec3::new(1.0, 0.5, 0.0)),
+                ..default()
+            },
+        ]
+    }
+
+    /// elapsed times covering progress from 0 to 1.2 through `decay`
+    fn elapsed_times(decay: &PixelDecay) -> impl Iterator<Item = f64> + '_ {
+        (0..=120).map(|i| i as f64 / 100.0 * decay.duration.max(1.0))
+    }
+
+    #[test]
+    fn equal_decays_share_a_slot() {
+        let mut table = PixelDecays::default();
+        table.reset(&PixelDecay::default());
+
+        for decay in decays() {
+            if table.position(&decay).is_none() {
+                table.push(decay);
+            }
+        }
+
+        assert_eq!(table.len(), 3);
+
+        for (index, decay) in decays().iter().enumerate() {
+            assert_eq!(table.position(decay), Some(index as u32));
+        }
+
+        let negative_zero = PixelDecay {
+            floor: -0.0,
+            ..default()
+        };
+
+        assert_eq!(table.position(&negative_zero), Some(0));
+    }
+
+    #[test]
+    fn decays_past_the_limit_use_the_first() {
+        let mut table = PixelDecays::default();
+        table.reset(&PixelDecay::default());
+
+        for i in 1..MAX_PIXEL_DECAYS {
+            table.push(PixelDecay {
+                duration: i as f64,
+                ..default()
+            });
+        }
+
+        let extra = PixelDecay {
+            duration: -1.0,
+            ..default()
+        };
+
+        assert_eq!(table.push(extra.clone()), 0);
+        assert_eq!(table.len(), MAX_PIXEL_DECAYS);
+        assert_eq!(table.position(&extra), None);
+
+        table.reset(&extra);
+
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.position(&extra), Some(0));
+    }
+
+    #[test]
+    fn gpu_brightness_matches_cpu() {
+        for decay in decays() {
+            let gpu = GpuPixelDecay::bake(&decay);
+
+            for elapsed in elapsed_times(&decay) {
+                let cpu = decay.brightness(elapsed) as f32;
+                let baked = gpu.brightness(elapsed as f32);
+
+                assert!(
+                    (cpu - baked).abs() < TOLERANCE,
+                    "{decay:?} at {elapsed}: {cpu} != {baked}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn gpu_channel_factors_match_cpu() {
+        for decay in decays() {
+            let gpu = GpuPixelDecay::bake(&decay);
+
+            for elapsed in elapsed_times(&decay) {
+                let cpu = decay.channel_factors(elapsed);
+                let cpu = Vec3::new(cpu.red, cpu.green, cpu.blue);
+                let baked = gpu.channel_factors(elapsed as f32);
+
+                assert!(
+                    (cpu - baked).abs().max_element() < TOLERANCE,
+                    "{decay:?} at {elapsed}: {cpu} != {baked}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn zero_duration_is_already_faded() {
+        let decay = PixelDecay {
+            duration: 0.0,
+            floor: 0.1,
+            ..default()
+        };
+        let gpu = GpuPixelDecay::bake(&decay);
+
+        assert!((decay.brightness(0.0) - 0.1).abs() < 1e-9);
+        assert!((gpu.brightness(0.0) - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn without_channel_rates_colour_is_kept() {
+        let decay = PixelDecay::default();
+        let gpu = GpuPixelDecay::bake(&decay);
+
+        assert_eq!(decay.channel_factors(1000.0), LinearRgba::WHITE);
+        assert_eq!(gpu.channel_factors(1000.0), Vec3::ONE);
+    }
+}
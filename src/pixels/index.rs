@@ -0,0 +1,205 @@
+use bevy::{
+    ecs::{component::ComponentId, world::DeferredWorld},
+    prelude::*,
+};
+
+use crate::grid::position::GridPosition;
+
+use super::components::Pixel;
+
+/// Maps every cell of the grid to its pixel entity, indexed by the packed position.
+///
+/// Kept up to date by the hooks on [`Pixel`], which is why a pixel's position can only be changed
+/// by inserting a new `Pixel`.
+#[derive(Resource, Default, Debug)]
+pub struct PixelGrid {
+    width: i32,
+    height: i32,
+    entities: Vec<Option<Entity>>,
+}
+
+impl PixelGrid {
+    pub fn get(&self, pos: &GridPosition) -> Option<Entity> {
+        if !self.matches(pos) {
+            return None;
+        }
+
+        self.entities.get(pos.packed as usize).copied().flatten()
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// every pixel that has been spawned, in packed order
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition, Entity)> + '_ {
+        self.entities
+            .iter()
+            .enumerate()
+            .filter_map(|(packed, entity)| {
+                entity.map(|entity| {
+                    (
+                        GridPosition {
+                            width: self.width,
+                            height: self.height,
+                            packed: packed as i32,
+                        },
+                        entity,
+                    )
+                })
+            })
+    }
+
+    fn matches(&self, pos: &GridPosition) -> bool {
+        pos.width == self.width && pos.height == self.height
+    }
+
+    fn insert(&mut self, pos: &GridPosition, entity: Entity) {
+        // the first pixel of a differently sized grid replaces the old layout
+        if !self.matches(pos) {
+            self.width = pos.width;
+            self.height = pos.height;
+            self.entities.clear();
+            self.entities
+                .resize((pos.width * pos.height).max(0) as usize, None);
+        }
+
+        if let Some(slot) = self.entities.get_mut(pos.packed as usize) {
+            *slot = Some(entity);
+        } else {
+            warn!("pixel outside of its grid: {:?}", pos);
+        }
+    }
+
+    fn remove(&mut self, pos: &GridPosition, entity: Entity) {
+        if !self.matches(pos) {
+            return;
+        }
+
+        if let Some(slot) = self.entities.get_mut(pos.packed as usize) {
+            // another pixel may have taken the cell over already
+            if *slot == Some(entity) {
+                *slot = None;
+            }
+        }
+    }
+}
+
+pub(super) fn pixel_inserted_hook(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(pos) = world.get::<Pixel>(entity).map(|pixel| pixel.pos()) else {
+        return;
+    };
+
+    if let Some(mut grid) = world.get_resource_mut::<PixelGrid>() {
+        grid.insert(&pos, entity);
+    }
+}
+
+/// Runs before a `Pixel` is overwritten or removed (including on despawn).
+pub(super) fn pixel_replaced_hook(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(pos) = world.get::<Pixel>(entity).map(|pixel| pixel.pos()) else {
+        return;
+    };
+
+    if let Some(mut grid) = world.get_resource_mut::<PixelGrid>() {
+        grid.remove(&pos, entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<PixelGrid>();
+        world
+    }
+
+    fn pos(x: i32, y: i32) -> GridPosition {
+        GridPosition::new(5, 3, x, y)
+    }
+
+    fn grid(world: &World) -> &PixelGrid {
+        world.resource::<PixelGrid>()
+    }
+
+    #[test]
+    fn spawned_pixels_are_indexed() {
+        let mut world = world();
+
+        let a = world.spawn(Pixel::new(pos(1, 2))).id();
+        let b = world.spawn(Pixel::new(pos(4, 0))).id();
+
+        assert_eq!(grid(&world).get(&pos(1, 2)), Some(a));
+        assert_eq!(grid(&world).get(&pos(4, 0)), Some(b));
+        assert_eq!(grid(&world).get(&pos(0, 0)), None);
+        assert_eq!((grid(&world).width(), grid(&world).height()), (5, 3));
+
+        let indexed = grid(&world).iter().collect::<Vec<_>>();
+        assert_eq!(indexed, [(pos(4, 0), b), (pos(1, 2), a)]);
+    }
+
+    #[test]
+    fn inserting_a_new_pixel_moves_it() {
+        let mut world = world();
+
+        let a = world.spawn(Pixel::new(pos(1, 2))).id();
+        world.entity_mut(a).insert(Pixel::new(pos(3, 1)));
+
+        assert_eq!(grid(&world).get(&pos(1, 2)), None);
+        assert_eq!(grid(&world).get(&pos(3, 1)), Some(a));
+        assert_eq!(world.get::<Pixel>(a).unwrap().pos(), pos(3, 1));
+    }
+
+    #[test]
+    fn taking_over_a_cell_survives_the_old_pixel() {
+        let mut world = world();
+
+        let a = world.spawn(Pixel::new(pos(1, 1))).id();
+        let b = world.spawn(Pixel::new(pos(1, 1))).id();
+
+        assert_eq!(grid(&world).get(&pos(1, 1)), Some(b));
+
+        // `a` no longer owns the cell, so despawning it leaves `b` in place
+        world.despawn(a);
+        assert_eq!(grid(&world).get(&pos(1, 1)), Some(b));
+
+        world.despawn(b);
+        assert_eq!(grid(&world).get(&pos(1, 1)), None);
+    }
+
+    #[test]
+    fn despawning_and_removing_clear_the_cell() {
+        let mut world = world();
+
+        let a = world.spawn(Pixel::new(pos(0, 0))).id();
+        let b = world.spawn(Pixel::new(pos(2, 1))).id();
+
+        world.despawn(a);
+        world.entity_mut(b).remove::<Pixel>();
+
+        assert_eq!(grid(&world).get(&pos(0, 0)), None);
+        assert_eq!(grid(&world).get(&pos(2, 1)), None);
+        assert_eq!(grid(&world).iter().count(), 0);
+    }
+
+    #[test]
+    fn a_different_grid_size_replaces_the_layout() {
+        let mut world = world();
+
+        world.spawn(Pixel::new(pos(1, 1)));
+
+        let resized = GridPosition::new(3, 5, 1, 1);
+        let b = world.spawn(Pixel::new(resized)).id();
+
+        assert_eq!((grid(&world).width(), grid(&world).height()), (3, 5));
+        assert_eq!(grid(&world).get(&resized), Some(b));
+        assert_eq!(grid(&world).get(&pos(1, 1)), None);
+        assert_eq!(grid(&world).iter().count(), 1);
+    }
+}
@@ -1,46 +1,77 @@
 pub mod components;
+pub mod decay;
+pub mod index;
+pub mod state;
 pub mod systems;
+pub mod timeline;
+pub mod user;
 
 use bevy::{
     app::{App, Update},
     ecs::{
-        schedule::{common_conditions::resource_exists, Condition, IntoSystemConfigs},
+        schedule::{
+            common_conditions::{resource_changed, resource_changed_or_removed, resource_exists},
+            Condition, IntoSystemConfigs,
+        },
         system::Single,
     },
     state::condition::in_state,
 };
 use bevy_window::Window;
+use decay::{PixelDecay, PixelDecays};
+use index::PixelGrid;
+use state::{
+    send_pixel_locked, update_pixel_states, PixelFaded, PixelLit, PixelLocked, PixelState,
+};
 use systems::{
-    position_pixels, update_pixel_brightness, update_pixel_lit_time, update_pixel_lit_time_run_if,
-    user_pixel_added_observer,
+    position_pixels, rebuild_pixel_grid, rebuild_user_pixel_easing, update_pixel_instances,
+    update_pixel_lit_time,
 };
+use timeline::rebuild_scan_timeline;
+use user::{user_pixel_added_observer, user_pixel_removed_observer, UserPixelMoved, UserPixels};
 
 use crate::{
-    grid::position::GridPosition,
-    scenes::{story::PixelStates, SceneState},
+    grid::{config::GridConfig, mask::GridMask},
+    scenes::{cursor::sync_scan_cursors, story::PixelStates, SceneState},
     utils::run_if::has_window,
 };
 
-pub const SCANLINE_X: i32 = 21;
-pub const SCANLINE_Y: i32 = 11;
-pub const PACKED_SIZE: i32 = GridPosition::pack(SCANLINE_X, SCANLINE_X - 1, SCANLINE_Y - 1);
-pub const STARTING_USER_PIXEL: GridPosition =
-    GridPosition::new(SCANLINE_X, SCANLINE_Y, SCANLINE_X / 2, SCANLINE_Y / 2);
 pub const USER_PIXEL_OUTLINE_THICKNESS: f32 = 2.0;
-pub const PIXEL_GAP: f32 = 5.0;
-pub const PIXEL_SIZE: f32 = 56.0;
 pub const PIXEL_WAIT_TIME: f64 = 50.0;
 
 pub fn plugin(app: &mut App) {
+    app.register_type::<GridConfig>();
+    app.register_type::<PixelDecay>();
+    app.register_type::<PixelState>();
+    app.init_resource::<GridConfig>();
+    app.init_resource::<PixelGrid>();
+    app.init_resource::<PixelDecays>();
+    app.init_resource::<UserPixels>();
+    app.add_event::<PixelLit>();
+    app.add_event::<PixelFaded>();
+    app.add_event::<PixelLocked>();
+    app.add_event::<UserPixelMoved>();
+
     app.add_observer(user_pixel_added_observer);
+    app.add_observer(user_pixel_removed_observer);
     app.add_systems(
         Update,
         (
-            update_pixel_brightness,
-            update_pixel_lit_time
-                .run_if(resource_exists::<PixelStates>.and(update_pixel_lit_time_run_if)),
+            rebuild_pixel_grid.run_if(
+                resource_exists::<PixelStates>.and(
+                    resource_changed::<GridConfig>.or(resource_changed_or_removed::<GridMask>),
+                ),
+            ),
+            rebuild_user_pixel_easing
+                .run_if(resource_exists::<PixelStates>)
+                .after(sync_scan_cursors),
+            rebuild_scan_timeline,
+            update_pixel_lit_time,
+            (update_pixel_states, send_pixel_locked),
+            update_pixel_instances,
             position_pixels.run_if(has_window),
         )
+            .chain()
             .distributive_run_if(in_state(SceneState::Game)),
     );
 }
@@ -0,0 +1,313 @@
+use bevy::prelude::*;
+
+use crate::{
+    grid::position::GridPosition,
+    scenes::{clock::game_millis, story::LevelSettings},
+};
+
+use super::{
+    components::{Pixel, PixelLifetime, UserPixelMarker},
+    decay::PixelDecay,
+};
+
+/// Where a pixel is in its lifecycle. The scan lights pixels and their decay fades them back to
+/// `Off`, or down to its floor if it has one. `Locked` and `Disabled` are only ever set by
+/// gameplay, and the scan leaves them alone.
+#[derive(Component, Reflect, Default, Debug, PartialEq, Eq, Copy, Clone)]
+#[reflect(Component)]
+pub enum PixelState {
+    /// not lit, or finished fading
+    #[default]
+    Off,
+    /// lit by a cursor this frame
+    Lit,
+    /// fading out after being lit, or held at a decay's floor until it is lit again
+    Fading,
+    /// held at full brightness
+    Locked,
+    /// kept dark
+    Disabled,
+}
+
+impl PixelState {
+    /// Whether the scan lights the pixel when it reaches it.
+    pub fn is_scanned(&self) -> bool {
+        !matches!(self, PixelState::Locked | PixelState::Disabled)
+    }
+}
+
+/// Sent when a cursor lights a pixel.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PixelLit {
+    pub entity: Entity,
+    pub pos: GridPosition,
+    /// `ScanCursor::index` of the cursor that lit it
+    pub cursor: usize,
+    /// game clock milliseconds the pixel was lit at
+    pub lit_time: f64,
+}
+
+/// Sent when a pixel has finished fading and turned `Off`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PixelFaded {
+    pub entity: Entity,
+    pub pos: GridPosition,
+}
+
+/// Sent when a pixel's state is set to `Locked`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PixelLocked {
+    pub entity: Entity,
+    pub pos: GridPosition,
+}
+
+/// Moves pixels lit last frame on to `Fading`, and fading pixels whose decay has run its course to
+/// `Off`. Pixels whose decay has a floor are still lit once it has run, so they stay `Fading`.
+/// Runs after the scan, so pixels it lit this frame stay `Lit` until the next one.
+#[allow(clippy::type_complexity)]
+pub(super) fn update_pixel_states(
+    time: Res<Time<Virtual>>,
+    level: Res<LevelSettings>,
+    mut faded: EventWriter<PixelFaded>,
+    mut query: Query<(
+        Entity,
+        &Pixel,
+        Ref<PixelLifetime>,
+        Option<&PixelDecay>,
+        Has<UserPixelMarker>,
+        &mut PixelState,
+    )>,
+) {
+    let millis_elapsed = game_millis(&time);
+
+    for (entity, pixel, lifetime, decay, is_user_pixel, mut state) in &mut query {
+        match *state {
+            PixelState::Lit if !lifetime.is_changed() => {
+                *state = PixelState::Fading;
+            }
+            PixelState::Fading => {
+                let decay = level.decay_for(decay, is_user_pixel);
+
+                if decay.floor <= 0.0 && millis_elapsed - **lifetime >= decay.duration {
+                    *state = PixelState::Off;
+
+                    faded.send(PixelFaded {
+                        entity,
+                        pos: pixel.pos(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Sends [`PixelLocked`] for every pixel whose state was just set to `Locked`.
+pub(super) fn send_pixel_locked(
+    mut locked: EventWriter<PixelLocked>,
+    query: Query<(Entity, &Pixel, &PixelState), Changed<PixelState>>,
+) {
+    for (entity, pixel, state) in &query {
+        if *state == PixelState::Locked {
+            locked.send(PixelLocked {
+                entity,
+                pos: pixel.pos(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::world::CommandQueue;
+
+    use crate::{
+        grid::{config::GridConfig, field::InfluenceField},
+        pixels::{
+            index::PixelGrid,
+            systems::{spawn_pixel_grid, update_pixel_lit_time},
+        },
+        scenes::{
+            cursor::{CursorSettings, ScanCursor},
+            story::CombinedBellEasing,
+            sweep::{CursorPassedUserPixel, RowStarted, ScanSweeps, SweepCompleted, SweepStarted},
+        },
+    };
+
+    use super::*;
+
+    const GRID: GridConfig = GridConfig {
+        width: 2,
+        height: 1,
+        pixel_size: 56.0,
+        gap: 5.0,
+    };
+
+    /// the pixel at (0, 0) at the end of a frame, and how many of each event it got during it
+    #[derive(Debug, Default, PartialEq, Clone, Copy)]
+    struct Frame {
+        state: PixelState,
+        lit: usize,
+        faded: usize,
+        locked: usize,
+    }
+
+    #[derive(Resource, Default)]
+    struct Frames(Vec<Frame>);
+
+    fn record_frame(
+        pixels: Res<PixelGrid>,
+        states: Query<&PixelState>,
+        mut lit: EventReader<PixelLit>,
+        mut faded: EventReader<PixelFaded>,
+        mut locked: EventReader<PixelLocked>,
+        mut frames: ResMut<Frames>,
+    ) {
+        let pixel = pixels.get(&GRID.position(0, 0)).unwrap();
+
+        frames.0.push(Frame {
+            state: *states.get(pixel).unwrap(),
+            lit: lit.read().filter(|event| event.entity == pixel).count(),
+            faded: faded.read().filter(|event| event.entity == pixel).count(),
+            locked: locked.read().filter(|event| event.entity == pixel).count(),
+        });
+    }
+
+    /// Runs the scan over a 2x1 grid for `frames` frames of 10ms, with the pixel at (0, 0)
+    /// starting in `state`. The cursor lights it at 0ms and takes 5s to move on, so it is only lit
+    /// once.
+    fn run(state: PixelState, decay: PixelDecay, frames: usize) -> Vec<Frame> {
+        let mut app = App::new();
+        app.init_resource::<Time<Virtual>>();
+        app.insert_resource(LevelSettings { decay, ..default() });
+        app.init_resource::<PixelGrid>();
+        app.init_resource::<ScanSweeps>();
+        app.init_resource::<Frames>();
+        app.add_event::<PixelLit>();
+        app.add_event::<PixelFaded>();
+        app.add_event::<PixelLocked>();
+        app.add_event::<SweepStarted>();
+        app.add_event::<SweepCompleted>();
+        app.add_event::<RowStarted>();
+        app.add_event::<CursorPassedUserPixel>();
+        app.add_systems(
+            Update,
+            (
+                update_pixel_lit_time,
+                (update_pixel_states, send_pixel_locked),
+                record_frame,
+            )
+                .chain(),
+        );
+
+        let world = app.world_mut();
+
+        let mut queue = CommandQueue::default();
+        spawn_pixel_grid(&mut Commands::new(&mut queue, world), &GRID, None, &[]);
+        queue.apply(world);
+
+        let pixel = world
+            .resource::<PixelGrid>()
+            .get(&GRID.position(0, 0))
+            .unwrap();
+        world.entity_mut(pixel).insert(state);
+
+        world.spawn((
+            ScanCursor::new(0, &CursorSettings::default(), &GRID, None, 0.0),
+            CombinedBellEasing::new(100.0),
+            InfluenceField::default(),
+        ));
+
+        for _ in 0..frames {
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(10));
+            app.update();
+        }
+
+        app.world_mut().remove_resource::<Frames>().unwrap().0
+    }
+
+    fn decay(floor: f64) -> PixelDecay {
+        PixelDecay {
+            duration: 100.0,
+            floor,
+            ..default()
+        }
+    }
+
+    fn totals(frames: &[Frame]) -> (usize, usize, usize) {
+        frames
+            .iter()
+            .fold((0, 0, 0), |(lit, faded, locked), frame| {
+                (lit + frame.lit, faded + frame.faded, locked + frame.locked)
+            })
+    }
+
+    #[test]
+    fn lit_pixels_fade_then_turn_off() {
+        let frames = run(PixelState::Off, decay(0.0), 100);
+
+        assert_eq!(
+            frames[0],
+            Frame {
+                state: PixelState::Lit,
+                lit: 1,
+                ..default()
+            }
+        );
+
+        // lit at 0ms, so the 100ms decay runs out on the tenth frame
+        assert!(frames[1..9]
+            .iter()
+            .all(|frame| frame.state == PixelState::Fading));
+        assert_eq!(
+            frames[9],
+            Frame {
+                state: PixelState::Off,
+                faded: 1,
+                ..default()
+            }
+        );
+        assert!(frames[10..]
+            .iter()
+            .all(|frame| frame.state == PixelState::Off));
+
+        assert_eq!(totals(&frames), (1, 1, 0));
+    }
+
+    #[test]
+    fn pixels_with_a_floor_stay_fading() {
+        let frames = run(PixelState::Off, decay(0.25), 100);
+
+        assert_eq!(frames[0].state, PixelState::Lit);
+        assert!(frames[1..]
+            .iter()
+            .all(|frame| frame.state == PixelState::Fading));
+
+        assert_eq!(totals(&frames), (1, 0, 0));
+    }
+
+    #[test]
+    fn locked_pixels_stay_locked() {
+        let frames = run(PixelState::Locked, decay(0.0), 100);
+
+        assert_eq!(frames[0].locked, 1);
+        assert!(frames.iter().all(|frame| frame.state == PixelState::Locked));
+
+        assert_eq!(totals(&frames), (0, 0, 1));
+    }
+
+    #[test]
+    fn disabled_pixels_stay_disabled() {
+        let frames = run(PixelState::Disabled, decay(0.0), 100);
+
+        assert!(frames
+            .iter()
+            .all(|frame| frame.state == PixelState::Disabled));
+
+        assert_eq!(totals(&frames), (0, 0, 0));
+    }
+}
@@ -1,126 +1,1010 @@
 use bevy::{prelude::*, utils::warn};
-use bevy_mod_index::index::Index;
 
 use crate::{
-    grid::position::GridPosition,
-    materials::rect_outlined::OutlinedRectMaterial,
-    scenes::story::{CombinedBellEasing, PixelStates},
+    easings::{asset::EasingAsset, expr::BellShape},
+    grid::{config::GridConfig, field::InfluenceField, mask::GridMask, position::GridPosition},
+    materials::pixel_instances::PixelInstance,
+    scenes::{
+        clock::game_millis,
+        cursor::{LightCombine, ScanCursor},
+        story::{CombinedBellEasing, LevelSettings, PixelStates, ScanEasing},
+        sweep::SweepTracker,
+        SceneState,
+    },
 };
 
 use super::{
-    components::{AllPixels, Pixel, PixelLifetime, UserPixelMarker},
-    PIXEL_WAIT_TIME, SCANLINE_X, SCANLINE_Y, USER_PIXEL_OUTLINE_THICKNESS,
+    components::{Pixel, PixelColor, PixelLifetime, PixelLights, UserPixelMarker},
+    decay::{PixelDecay, PixelDecays},
+    index::PixelGrid,
+    state::{PixelLit, PixelState},
+    PIXEL_WAIT_TIME,
 };
 
-pub fn set_user_pixel(commands: &mut Commands, mut index: Index<AllPixels>, pos: GridPosition) {
-    if let Ok(entity) = index.lookup_single(&pos) {
-        commands.entity(entity).insert(UserPixelMarker);
-    } else {
-        warn!("unable to set pixel to user pixel: {}", pos.packed);
-    }
-}
-
-pub(super) fn update_pixel_brightness(
-    time: Res<Time>,
-    mut materials: ResMut<Assets<OutlinedRectMaterial>>,
-    query: Query<
-        (
-            &Pixel,
-            &PixelLifetime,
-            &MeshMaterial2d<OutlinedRectMaterial>,
-        ),
-        // // dont update brightness of user pixels
-        // Without<UserPixelMarker>,
-    >,
+/// Spawns one pixel entity per grid cell (skipping any missing from `mask`), marking every
+/// position in `user_pixels` as a user pixel. User pixels on a missing cell move to the nearest
+/// cell that has a pixel.
+pub fn spawn_pixel_grid(
+    commands: &mut Commands,
+    config: &GridConfig,
+    mask: Option<&GridMask>,
+    user_pixels: &[(GridPosition, UserPixelMarker)],
 ) {
-    let millis_elapsed = time.elapsed().as_millis() as f64;
+    let user_pixels = user_pixels
+        .iter()
+        .filter_map(|(pos, marker)| match mask {
+            Some(mask) => Some((mask.nearest(pos)?, *marker)),
+            None => Some((*pos, *marker)),
+        })
+        .collect::<Vec<_>>();
 
-    for (_, lifetime, mat_handle) in &query {
-        let brightness =
-            (1.0 - ((millis_elapsed - **lifetime) / 1000.0 / 6.0).clamp(0.0, 1.0)).powf(3.0);
+    for x in 0..config.width {
+        for y in 0..config.height {
+            if mask.is_some_and(|mask| !mask.get(IVec2::new(x, y))) {
+                continue;
+            }
 
-        if let Some(mat) = materials.get_mut(&mat_handle.0) {
-            mat.rect_color.alpha = brightness as f32;
+            let pos = config.position(x, y);
+
+            let mut pixel = commands.spawn((
+                StateScoped(SceneState::Game),
+                Pixel::new(pos),
+                PixelColor(0),
+                PixelLifetime(0.0),
+                PixelInstance {
+                    color: pixel_base_color(&pos),
+                    ..default()
+                },
+            ));
+
+            if let Some((_, marker)) = user_pixels.iter().find(|(user_pos, _)| *user_pos == pos) {
+                pixel.insert(*marker);
+            }
         }
     }
 }
 
-pub(super) fn update_pixel_lit_time(
-    time: Res<Time>,
+/// Respawns the pixel grid when `GridConfig` changes its layout or the `GridMask` changes,
+/// carrying the scan cursors and user pixels over to the new grid.
+pub(super) fn rebuild_pixel_grid(
+    mut commands: Commands,
+    config: Res<GridConfig>,
+    mask: Option<Res<GridMask>>,
     mut state: ResMut<PixelStates>,
-    mut query: Query<
-        (&Pixel, &mut PixelLifetime), // , Without<UserPixelMarker>
-    >,
-    bell_easing: Res<CombinedBellEasing>,
-    mut index: Index<AllPixels>,
+    mut cursors: Query<&mut ScanCursor>,
+    pixels: Query<(Entity, &Pixel, Option<&UserPixelMarker>)>,
 ) {
-    let millis_elapsed = time.elapsed().as_millis() as f64;
-
-    // these should always exist
-    let pixel_entity = if let Ok(entity) = index.lookup_single(&state.next_lit_pixel) {
-        entity
-    } else {
-        warn!(
-            "failed to get next lit pixel for: {:?}",
-            state.next_lit_pixel.packed
-        );
-        state.update_next_pixel();
+    // `validate_grid_config` fixes this up before the next frame
+    if !config.is_valid() {
         return;
-    };
+    }
 
-    let (_, mut lit_pixel_lifetime) = if let Ok(entity) = query.get_mut(pixel_entity) {
-        entity
-    } else {
-        warn!(
-            "failed to get next lit pixel lifetime for: {:?}",
-            state.next_lit_pixel.packed
-        );
+    let mask = mask.map(|mask| mask.clone());
+
+    if config.same_layout(&state.grid) && mask == state.mask {
+        // only the gap or pixel size changed, which `position_pixels` and the renderer pick up on
+        // their own
+        state.grid = *config;
         return;
+    }
+
+    let user_pixels = pixels
+        .iter()
+        .filter_map(|(_, pixel, marker)| Some((config.remap(pixel.pos()), *marker?)))
+        .collect::<Vec<_>>();
+
+    for (entity, ..) in &pixels {
+        commands.entity(entity).despawn();
+    }
+
+    spawn_pixel_grid(&mut commands, &config, mask.as_ref(), &user_pixels);
+
+    for mut cursor in &mut cursors {
+        cursor.reorder(&config, mask.as_ref());
+    }
+
+    *state = PixelStates {
+        grid: *config,
+        mask,
     };
+}
+
+/// The colour a pixel is tinted by the cursors that light it, a gradient across the grid.
+pub fn pixel_base_color(pos: &GridPosition) -> LinearRgba {
+    let coords = pos.unpacked();
+
+    LinearRgba {
+        red: (coords.x as f32 / pos.width as f32),
+        green: 1.0,
+        blue: (coords.y as f32 / pos.height as f32),
+        alpha: 1.0,
+    }
+}
+
+/// Points every pixel's `PixelInstance` at its most recent light and its `PixelDecay`, for the
+/// shader to fade. Pixels are only touched when they are lit or their decay or tint changes.
+///
+/// `PixelDecays` is rebuilt from scratch whenever a decay changes or goes away, so it only ever
+/// holds the decays in use.
+///
+/// Fading the most recent light is what `LightCombine::Latest` shows, and what `LightCombine::Max`
+/// shows for decays that only ever get dimmer. The shader can't add lights together, so for
+/// `LightCombine::Add` every pixel's brightness is worked out here each frame instead.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(super) fn update_pixel_instances(
+    time: Res<Time<Virtual>>,
+    level: Res<LevelSettings>,
+    mut decays: ResMut<PixelDecays>,
+    mut last_tints: Local<Vec<LinearRgba>>,
+    cursors: Query<&ScanCursor>,
+    mut removed_user_pixels: RemovedComponents<UserPixelMarker>,
+    mut removed_decays: RemovedComponents<PixelDecay>,
+    changed_decays: Query<(), Changed<PixelDecay>>,
+    mut query: Query<(
+        &Pixel,
+        Ref<PixelLifetime>,
+        Ref<PixelLights>,
+        Option<Ref<PixelDecay>>,
+        Option<Ref<UserPixelMarker>>,
+        Ref<PixelState>,
+        &mut PixelInstance,
+    )>,
+) {
+    let millis_elapsed = game_millis(&time);
+
+    let mut tints = Vec::new();
+
+    for cursor in &cursors {
+        if tints.len() <= cursor.index {
+            tints.resize(cursor.index + 1, LinearRgba::WHITE);
+        }
+
+        tints[cursor.index] = cursor.color;
+    }
+
+    let tints_changed = tints != *last_tints;
+    *last_tints = tints;
+    let tints = &*last_tints;
+
+    let decays_removed = removed_decays.read().count() > 0;
+    let rebuild_decays =
+        level.is_changed() || decays_removed || !changed_decays.is_empty() || decays.is_empty();
+
+    if rebuild_decays {
+        decays.reset(&level.decay);
+    }
+
+    let update_all = rebuild_decays || tints_changed || removed_user_pixels.read().count() > 0;
+    let combine_on_cpu = level.light_combine == LightCombine::Add;
+
+    for (pixel, lifetime, lights, decay, user_pixel, state, mut instance) in &mut query {
+        let changed = lifetime.is_changed()
+            || lights.is_changed()
+            || state.is_changed()
+            || decay.as_ref().is_some_and(|decay| decay.is_changed())
+            || user_pixel.as_ref().is_some_and(|marker| marker.is_added());
+
+        if !update_all && !combine_on_cpu && !changed {
+            continue;
+        }
+
+        let decay = level.decay_for(decay.as_deref(), user_pixel.is_some());
+
+        let decay_index = match decays.position(decay) {
+            Some(index) => index,
+            None => decays.push(decay.clone()),
+        };
+
+        let lit = lights
+            .iter()
+            .enumerate()
+            .filter(|(_, lit_time)| lit_time.is_finite())
+            .map(|(index, &lit_time)| {
+                let tint = tints.get(index).copied().unwrap_or(LinearRgba::WHITE);
+
+                (tint, lit_time)
+            });
+
+        let base = pixel_base_color(&pixel.pos());
+
+        let (tint, lit_time, brightness) = if let Some(brightness) = match *state {
+            PixelState::Locked => Some(1.0),
+            PixelState::Disabled => Some(0.0),
+            _ => None,
+        } {
+            (LinearRgba::WHITE, **lifetime, Some(brightness))
+        } else if combine_on_cpu {
+            let light = |(tint, lit_time): (LinearRgba, f64)| {
+                let elapsed = millis_elapsed - lit_time;
+                let channels = decay.channel_factors(elapsed);
+
+                (
+                    decay.brightness(elapsed),
+                    LinearRgba {
+                        red: tint.red * channels.red,
+                        green: tint.green * channels.green,
+                        blue: tint.blue * channels.blue,
+                        alpha: tint.alpha,
+                    },
+                    lit_time,
+                )
+            };
+
+            // pixels no cursor has reached yet keep fading from when they were spawned
+            let (brightness, tint) =
+                level
+                    .light_combine
+                    .apply(lit.map(light))
+                    .unwrap_or_else(|| {
+                        let (brightness, tint, _) = light((LinearRgba::WHITE, **lifetime));
+
+                        (brightness, tint)
+                    });
+
+            (tint, **lifetime, Some(brightness as f32))
+        } else {
+            let (tint, lit_time) = lit
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap_or((LinearRgba::WHITE, **lifetime));
+
+            (tint, lit_time, None)
+        };
+
+        instance.color = LinearRgba {
+            red: base.red * tint.red,
+            green: base.green * tint.green,
+            blue: base.blue * tint.blue,
+            alpha: 1.0,
+        };
+        instance.lit_time = lit_time;
+        instance.decay = decay_index;
+        instance.brightness = brightness;
+    }
+}
+
+/// Lights every pixel whose scheduled time has passed since the last frame, each stamped with the
+/// time it was scheduled for rather than the frame time, so the scan keeps the same timing at any
+/// frame rate.
+pub(crate) fn update_pixel_lit_time(
+    time: Res<Time<Virtual>>,
+    mut cursors: Query<(&mut ScanCursor, &CombinedBellEasing, &InfluenceField)>,
+    mut query: Query<(
+        &mut PixelLifetime,
+        &mut PixelLights,
+        &mut PixelState,
+        Has<UserPixelMarker>,
+    )>,
+    pixels: Res<PixelGrid>,
+    mut lit: EventWriter<PixelLit>,
+    mut sweeps: SweepTracker,
+) {
+    let millis_elapsed = game_millis(&time);
+
+    for (mut cursor, bell_easing, field) in &mut cursors {
+        if cursor.order.is_empty() {
+            continue;
+        }
+
+        // when the sweep started and how many pixels into it the cursor is, to catch sweeps
+        // that take no time at all
+        let mut sweep_start = cursor.next_lit_time;
+        let mut sweep_steps = 0;
+
+        while cursor.next_lit_time <= millis_elapsed {
+            if sweep_steps == cursor.order.len() {
+                // Every pixel of the scan waits 0ms, so this would never catch up. Light them all
+                // once more at the frame time and wait for the next frame instead.
+                if cursor.next_lit_time <= sweep_start {
+                    if cursor.next_lit_time >= millis_elapsed {
+                        break;
+                    }
+
+                    cursor.next_lit_time = millis_elapsed;
+                }
+
+                sweep_start = cursor.next_lit_time;
+                sweep_steps = 0;
+            }
+
+            sweep_steps += 1;
+
+            let lit_time = cursor.next_lit_time;
+
+            // these should always exist
+            let pixel_entity = if let Some(entity) = pixels.get(&cursor.next_lit_pixel) {
+                entity
+            } else {
+                warn!(
+                    "failed to get next lit pixel for: {:?}",
+                    cursor.next_lit_pixel.packed
+                );
+                cursor.update_next_pixel();
+                continue;
+            };
+
+            let (mut lit_pixel_lifetime, mut lights, mut state, is_user_pixel) =
+                if let Ok(entity) = query.get_mut(pixel_entity) {
+                    entity
+                } else {
+                    warn!(
+                        "failed to get next lit pixel lifetime for: {:?}",
+                        cursor.next_lit_pixel.packed
+                    );
+                    break;
+                };
 
-    **lit_pixel_lifetime = millis_elapsed;
+            sweeps.step(cursor.index, cursor.step, &cursor.next_lit_pixel, lit_time);
 
-    state.update_next_pixel();
+            if is_user_pixel {
+                sweeps.passed_user_pixel(cursor.index, pixel_entity, lit_time);
+            }
 
-    let x = state.next_lit_pixel.normalised();
+            if state.is_scanned() {
+                **lit_pixel_lifetime = lit_pixel_lifetime.max(lit_time);
 
-    state.next_lit_time = millis_elapsed + (PIXEL_WAIT_TIME * bell_easing.evaluate((x, 0.2, 1.0)));
+                if lights.len() <= cursor.index {
+                    lights.resize(cursor.index + 1, f64::NEG_INFINITY);
+                }
+
+                lights[cursor.index] = lit_time;
+
+                state.set_if_neq(PixelState::Lit);
+
+                lit.send(PixelLit {
+                    entity: pixel_entity,
+                    pos: cursor.next_lit_pixel,
+                    cursor: cursor.index,
+                    lit_time,
+                });
+            }
+
+            cursor.update_next_pixel();
+
+            let easing = scan_easing_at(&cursor, bell_easing, field, &cursor.next_lit_pixel);
+
+            cursor.next_lit_time = lit_time + (PIXEL_WAIT_TIME * easing / cursor.speed);
+
+            if cursor.step == 0 {
+                sweeps.wrapped(cursor.index, cursor.next_lit_time);
+            }
+        }
+    }
 }
 
-pub fn update_pixel_lit_time_run_if(time: Res<Time>, state: Res<PixelStates>) -> bool {
-    let millis_elapsed = time.elapsed().as_millis() as f64;
+/// The easing value for `pos`, adding in the 2-D influence field when the level uses one.
+pub fn scan_easing_at(
+    cursor: &ScanCursor,
+    bell_easing: &CombinedBellEasing,
+    field: &InfluenceField,
+    pos: &GridPosition,
+) -> f64 {
+    let value = bell_easing.evaluate(cursor.order.normalised(pos));
 
-    millis_elapsed > state.next_lit_time
+    if field.is_empty() {
+        value
+    } else {
+        bell_easing.combinator().apply(value, field.sample(pos))
+    }
 }
 
-pub(super) fn position_pixels(window: Single<&Window>, mut query: Query<(&Pixel, &mut Transform)>) {
+pub(super) fn position_pixels(
+    window: Single<&Window>,
+    config: Res<GridConfig>,
+    mut query: Query<(&Pixel, &mut Transform)>,
+) {
     for (pixel, mut transform) in &mut query {
-        transform.translation = pixel.get_translation(&window);
+        transform.translation = pixel.get_translation(&window, &config);
+    }
+}
+
+/// Combines one bell curve per user pixel, centred on the point in the scan where that pixel is
+/// lit, with the terms from `asset` (or nothing, if it hasn't loaded yet). The result is baked
+/// once at the end using the asset's settings, rather than after every term.
+pub fn user_pixel_easing<'a>(
+    asset: Option<&EasingAsset>,
+    periodic: bool,
+    user_pixels: impl IntoIterator<Item = (f64, &'a UserPixelMarker)>,
+) -> CombinedBellEasing {
+    let (mut easing, default_bell) = match asset {
+        Some(asset) => (asset.to_combined(), asset.user_pixel_bell),
+        None => (CombinedBellEasing::new(0.0), BellShape::default()),
+    };
+
+    for (center, marker) in user_pixels {
+        easing.extend(
+            marker
+                .bell
+                .unwrap_or(default_bell)
+                .at(center)
+                .with_periodic(periodic),
+        );
     }
+
+    easing.with_baking(asset.and_then(|asset| asset.bake))
 }
 
-pub(super) fn user_pixel_added_observer(
-    trigger: Trigger<OnAdd, UserPixelMarker>,
-    query: Query<(&UserPixelMarker, &MeshMaterial2d<OutlinedRectMaterial>)>,
-    mut materials: ResMut<Assets<OutlinedRectMaterial>>,
-    mut bell_easing: ResMut<CombinedBellEasing>,
+/// Rebuilds each cursor's `CombinedBellEasing` (and `InfluenceField`, for levels that use one)
+/// whenever a user pixel is added, removed or changed, the scan order moves the user pixels to a
+/// different point in the scan, or the cursor's easing asset is (re)loaded.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn rebuild_user_pixel_easing(
+    state: Res<PixelStates>,
+    level: Res<LevelSettings>,
+    easing_assets: Res<Assets<EasingAsset>>,
+    mut asset_events: EventReader<AssetEvent<EasingAsset>>,
+    mut cursors: Query<(
+        Ref<ScanCursor>,
+        Ref<ScanEasing>,
+        &mut CombinedBellEasing,
+        &mut InfluenceField,
+    )>,
+    changed: Query<(), Changed<UserPixelMarker>>,
+    mut removed: RemovedComponents<UserPixelMarker>,
+    user_pixels: Query<(&Pixel, &UserPixelMarker)>,
 ) {
-    if let Ok((_, mat_handle)) = query.get(trigger.entity()) {
-        if let Some(mat) = materials.get_mut(&mat_handle.0) {
-            mat.outline_thickness = USER_PIXEL_OUTLINE_THICKNESS;
+    let any_removed = removed.read().count() > 0;
+    let user_pixels_changed = !changed.is_empty() || any_removed || level.is_changed();
+
+    let loaded_assets = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (cursor, scan_easing, mut bell_easing, mut field) in &mut cursors {
+        let asset_changed = loaded_assets.contains(&scan_easing.0.id());
+
+        if !user_pixels_changed && !asset_changed && !cursor.is_added() && !scan_easing.is_changed()
+        {
+            continue;
+        }
+
+        let asset = easing_assets.get(&scan_easing.0);
+
+        match level.influence {
+            None => {
+                *bell_easing = user_pixel_easing(
+                    asset,
+                    level.periodic_easing,
+                    user_pixels
+                        .iter()
+                        .map(|(pixel, marker)| (cursor.order.normalised(&pixel.pos()), marker)),
+                );
+                *field = InfluenceField::default();
+            }
+            Some(metric) => {
+                let default_bell =
+                    asset.map_or_else(BellShape::default, |asset| asset.user_pixel_bell);
+
+                *bell_easing = user_pixel_easing(asset, level.periodic_easing, std::iter::empty());
+                *field = InfluenceField::new(
+                    metric,
+                    state.grid.width,
+                    state.grid.height,
+                    user_pixels
+                        .iter()
+                        .map(|(pixel, marker)| (pixel.pos(), marker.bell.unwrap_or(default_bell))),
+                );
+            }
         }
     }
+}
 
-    // TODO: use new struct methods
-    // let mut max_ease: Box<BellEasingFn> = CombinedEasing::default().0;
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
 
-    // for (marker, ..) in &query {
-    //     // done to keep the inner closure 'static
-    //     let pixel_bell_curve_gen = |center: f64| move |x: f64| bell_easing.evaluate(x, 0.2, 1.0);
-    //     let pixel_bell_curve = pixel_bell_curve_gen(marker.pos.normalised());
+    use bevy::ecs::world::CommandQueue;
 
-    //     max_ease = Box::new(move |x| max_ease(x).max(pixel_bell_curve(x)));
-    // }
+    use crate::{
+        easings::{expr::EasingExpr, Combinator},
+        grid::{
+            position::DistanceMetric,
+            scan_pattern::{ScanOrder, ScanPatternKind},
+        },
+        pixels::index::PixelGrid,
+        scenes::{
+            cursor::CursorSettings,
+            sweep::{CursorPassedUserPixel, RowStarted, ScanSweeps, SweepCompleted, SweepStarted},
+        },
+    };
+
+    use super::*;
+
+    /// spawns a grid into a new world, returning the positions of its user pixels
+    fn spawn_user_pixels(
+        config: &GridConfig,
+        mask: Option<&GridMask>,
+        user_pixels: &[GridPosition],
+    ) -> Vec<GridPosition> {
+        let mut world = World::new();
+        world.init_resource::<PixelGrid>();
+
+        let user_pixels = user_pixels
+            .iter()
+            .map(|&pos| (pos, UserPixelMarker::default()))
+            .collect::<Vec<_>>();
+
+        let mut queue = CommandQueue::default();
+        spawn_pixel_grid(
+            &mut Commands::new(&mut queue, &world),
+            config,
+            mask,
+            &user_pixels,
+        );
+        queue.apply(&mut world);
+
+        let mut positions = world
+            .query_filtered::<&Pixel, With<UserPixelMarker>>()
+            .iter(&world)
+            .map(|pixel| pixel.pos())
+            .collect::<Vec<_>>();
+
+        positions.sort();
+        positions
+    }
+
+    /// user pixels placed at `steps` of a 5x4 row major scan, as (normalised position, marker)
+    fn markers_at(steps: &[usize], bell: Option<BellShape>) -> Vec<(f64, UserPixelMarker)> {
+        let order = ScanOrder::new(&ScanPatternKind::RowMajor, 5, 4);
+
+        steps
+            .iter()
+            .map(|&step| {
+                let pos = GridPosition {
+                    width: 5,
+                    height: 4,
+                    packed: order.packed_at(step),
+                };
+
+                (order.normalised(&pos), UserPixelMarker { bell })
+            })
+            .collect()
+    }
 
-    // easing_res.0 = max_ease;
+    fn easing_for(markers: &[(f64, UserPixelMarker)], periodic: bool) -> CombinedBellEasing {
+        user_pixel_easing(
+            None,
+            periodic,
+            markers.iter().map(|(center, marker)| (*center, marker)),
+        )
+    }
+
+    #[test]
+    fn peaks_at_user_pixel_steps() {
+        let markers = markers_at(&[3, 12], None);
+        let easing = easing_for(&markers, false);
+
+        assert_eq!(markers[0].0, 0.15);
+        assert_eq!(markers[1].0, 0.6);
+
+        for &(center, _) in &markers {
+            assert!((easing.evaluate(center) - 1.0).abs() < 1e-12);
+            assert!(easing.evaluate(center - 0.05) < easing.evaluate(center));
+            assert!(easing.evaluate(center + 0.05) < easing.evaluate(center));
+        }
+
+        // halfway between the two bells, each one is `e^-(0.225 / 0.2)` high
+        let expected = (-0.225_f64 / BellShape::default().width).exp();
+        assert!((easing.evaluate(0.375) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn marker_bell_overrides_default() {
+        let narrow = BellShape {
+            width: 0.05,
+            sharpness: 2.0,
+        };
+
+        let markers = markers_at(&[10], Some(narrow));
+        let easing = easing_for(&markers, false);
+
+        assert!((easing.evaluate(0.5) - 1.0).abs() < 1e-12);
+
+        let expected = (-(0.1_f64 / 0.05).powi(2)).exp();
+        assert!((easing.evaluate(0.6) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn periodic_bells_wrap_around() {
+        let markers = markers_at(&[0], None);
+
+        let periodic = easing_for(&markers, true);
+        let clamped = easing_for(&markers, false);
+
+        assert!((periodic.evaluate(0.95) - periodic.evaluate(0.05)).abs() < 1e-12);
+        assert!(clamped.evaluate(0.95) < clamped.evaluate(0.05));
+    }
+
+    #[test]
+    fn no_user_pixels_is_flat() {
+        let easing = easing_for(&[], false);
+
+        for x in [0.0, 0.25, 0.5, 1.0] {
+            assert_eq!(easing.evaluate(x), 0.0);
+        }
+    }
+
+    #[test]
+    fn user_pixels_snap_to_the_mask() {
+        let config = GridConfig {
+            width: 5,
+            height: 3,
+            ..default()
+        };
+        let mask = GridMask::from_text("#####\n##.##\n#####").unwrap();
+
+        assert_eq!(
+            spawn_user_pixels(&config, None, &[config.center()]),
+            [config.center()]
+        );
+
+        // the center is a hole, so the user pixel moves to the first of its closest cells
+        assert_eq!(
+            spawn_user_pixels(&config, Some(&mask), &[config.center()]),
+            [config.position(2, 0)]
+        );
+
+        assert_eq!(
+            spawn_user_pixels(&config, Some(&GridMask::empty(5, 3)), &[config.center()]),
+            []
+        );
+    }
+
+    fn decay(duration: f64) -> PixelDecay {
+        PixelDecay {
+            duration,
+            ..default()
+        }
+    }
+
+    fn decay_indices(app: &mut App, pixels: &[Entity]) -> Vec<u32> {
+        pixels
+            .iter()
+            .map(|&pixel| app.world().get::<PixelInstance>(pixel).unwrap().decay)
+            .collect()
+    }
+
+    #[test]
+    fn decays_only_hold_what_pixels_use() {
+        let config = GridConfig {
+            width: 4,
+            height: 1,
+            ..default()
+        };
+
+        let mut app = App::new();
+        app.init_resource::<Time<Virtual>>();
+        app.init_resource::<LevelSettings>();
+        app.init_resource::<PixelDecays>();
+        app.init_resource::<PixelGrid>();
+        app.add_systems(Update, update_pixel_instances);
+
+        let world = app.world_mut();
+        let pixels = [
+            world.spawn(Pixel::new(config.position(0, 0))).id(),
+            world
+                .spawn((Pixel::new(config.position(1, 0)), decay(100.0)))
+                .id(),
+            world
+                .spawn((Pixel::new(config.position(2, 0)), decay(200.0)))
+                .id(),
+            world
+                .spawn((Pixel::new(config.position(3, 0)), decay(100.0)))
+                .id(),
+        ];
+
+        app.update();
+
+        assert_eq!(app.world().resource::<PixelDecays>().len(), 3);
+        assert_eq!(decay_indices(&mut app, &pixels), [0, 1, 2, 1]);
+
+        // the 200ms decay is no longer used, so it's dropped
+        app.world_mut().entity_mut(pixels[2]).insert(decay(100.0));
+        app.update();
+
+        assert_eq!(app.world().resource::<PixelDecays>().len(), 2);
+        assert_eq!(decay_indices(&mut app, &pixels), [0, 1, 1, 1]);
+
+        for &pixel in &pixels[1..] {
+            app.world_mut().entity_mut(pixel).remove::<PixelDecay>();
+        }
+        app.update();
+
+        assert_eq!(app.world().resource::<PixelDecays>().len(), 1);
+        assert_eq!(decay_indices(&mut app, &pixels), [0, 0, 0, 0]);
+
+        // nothing changed, so the table is left alone for the render world
+        let last_changed = app.world().resource_ref::<PixelDecays>().last_changed();
+        app.update();
+
+        assert_eq!(
+            app.world().resource_ref::<PixelDecays>().last_changed(),
+            last_changed
+        );
+    }
+
+    /// Runs `rebuild_user_pixel_easing` once over a 5x5 grid with user pixels at (1, 1) and
+    /// (4, 3), returning the app with the rebuilt cursor in it.
+    fn rebuild_easing(influence: Option<DistanceMetric>) -> App {
+        let config = GridConfig {
+            width: 5,
+            height: 5,
+            ..default()
+        };
+
+        let mut app = App::new();
+        app.insert_resource(PixelStates {
+            grid: config,
+            mask: None,
+        });
+        app.insert_resource(LevelSettings {
+            influence,
+            periodic_easing: false,
+            ..default()
+        });
+        app.init_resource::<Assets<EasingAsset>>();
+        app.add_event::<AssetEvent<EasingAsset>>();
+        app.add_systems(Update, rebuild_user_pixel_easing);
+
+        let world = app.world_mut();
+
+        let handle = world
+            .resource_mut::<Assets<EasingAsset>>()
+            .add(EasingAsset {
+                base: EasingExpr::Constant(0.1),
+                combinator: Combinator::Add,
+                terms: Vec::new(),
+                user_pixel_bell: BellShape {
+                    width: 0.25,
+                    sharpness: 1.0,
+                },
+                bake: None,
+            });
+
+        for pos in [config.position(1, 1), config.position(4, 3)] {
+            world.spawn((Pixel::new(pos), UserPixelMarker::default()));
+        }
+
+        world.spawn((
+            ScanCursor::new(0, &CursorSettings::default(), &config, None, 0.0),
+            ScanEasing(handle),
+            CombinedBellEasing::new(0.0),
+            InfluenceField::default(),
+        ));
+
+        app.update();
+        app
+    }
+
+    #[test]
+    fn influence_field_combines_with_the_scan_easing() {
+        let mut app = rebuild_easing(Some(DistanceMetric::Manhattan));
+        let world = app.world_mut();
+        let (cursor, easing, field) = world
+            .query::<(&ScanCursor, &CombinedBellEasing, &InfluenceField)>()
+            .single(world);
+
+        // the user pixels go into the field, leaving only the asset's own terms on the scan
+        assert!(easing.terms().is_empty());
+        assert_eq!(easing.combinator(), Combinator::Add);
+        assert!(!field.is_empty());
+        assert_eq!(field.metric(), DistanceMetric::Manhattan);
+
+        let config = GridConfig {
+            width: 5,
+            height: 5,
+            ..default()
+        };
+
+        for packed in 0..25 {
+            let pos = GridPosition {
+                width: 5,
+                height: 5,
+                packed,
+            };
+            let value = scan_easing_at(cursor, easing, field, &pos);
+
+            assert!(
+                (value - (0.1 + field.sample(&pos))).abs() < 1e-12,
+                "{pos:?}"
+            );
+        }
+
+        // on a user pixel, its bell adds a full 1.0 on top of the base
+        let value = scan_easing_at(cursor, easing, field, &config.position(4, 3));
+        assert!((value - 1.1).abs() < 1e-12);
+
+        // (2, 1) is one step from (1, 1) out of the 8 across the grid, with the asset's bell
+        let expected = 0.1 + (-(1.0_f64 / 8.0) / 0.25).exp();
+        let value = scan_easing_at(cursor, easing, field, &config.position(2, 1));
+        assert!((value - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn no_influence_puts_user_pixels_on_the_scan() {
+        let mut app = rebuild_easing(None);
+        let world = app.world_mut();
+        let (cursor, easing, field) = world
+            .query::<(&ScanCursor, &CombinedBellEasing, &InfluenceField)>()
+            .single(world);
+
+        assert!(field.is_empty());
+        assert_eq!(easing.terms().len(), 2);
+
+        let config = GridConfig {
+            width: 5,
+            height: 5,
+            ..default()
+        };
+        let pos = config.position(4, 3);
+        let value = scan_easing_at(cursor, easing, field, &pos);
+
+        // the field adds nothing, and the user pixel's own bell adds 1.0 on top of the base
+        assert_eq!(value, easing.evaluate(cursor.order.normalised(&pos)));
+        assert!(value >= 1.1 - 1e-12);
+    }
+
+    /// How fast the scan in `run_scan` goes, and how much it slows down around its user pixel.
+    #[derive(Clone, Copy)]
+    struct Scan {
+        base: f64,
+        user_pixel: Option<BellShape>,
+        speed: f64,
+    }
+
+    /// every pixel's lit time, and the step and next lit time the cursor ended up at
+    type ScanResult = (Vec<(GridPosition, f64)>, usize, f64);
+
+    /// Runs `scan` over a 5x3 grid for `frames` frames of `frame_time`.
+    fn run_scan(scan: Scan, frame_time: Duration, frames: u32) -> ScanResult {
+        let config = GridConfig {
+            width: 5,
+            height: 3,
+            ..default()
+        };
+
+        let mut app = App::new();
+        app.init_resource::<Time<Virtual>>();
+        app.init_resource::<PixelGrid>();
+        app.init_resource::<ScanSweeps>();
+        app.add_event::<PixelLit>();
+        app.add_event::<SweepStarted>();
+        app.add_event::<SweepCompleted>();
+        app.add_event::<RowStarted>();
+        app.add_event::<CursorPassedUserPixel>();
+        app.add_systems(Update, update_pixel_lit_time);
+
+        let world = app.world_mut();
+
+        let mut queue = CommandQueue::default();
+        spawn_pixel_grid(
+            &mut Commands::new(&mut queue, world),
+            &config,
+            None,
+            &[(config.position(3, 1), UserPixelMarker::default())],
+        );
+        queue.apply(world);
+
+        let settings = CursorSettings {
+            speed: scan.speed,
+            ..default()
+        };
+        let cursor = ScanCursor::new(0, &settings, &config, None, 0.0);
+
+        // slows down to a full `PIXEL_WAIT_TIME` around the user pixel, so the waits vary
+        let mut easing = CombinedBellEasing::new(scan.base);
+
+        if let Some(bell) = scan.user_pixel {
+            easing.extend(bell.at(cursor.order.normalised(&config.position(3, 1))));
+        }
+
+        world.spawn((cursor, easing, InfluenceField::default()));
+
+        for _ in 0..frames {
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(frame_time);
+            app.update();
+        }
+
+        let world = app.world_mut();
+
+        let mut lit = world
+            .query::<(&Pixel, &PixelLifetime)>()
+            .iter(world)
+            .map(|(pixel, lifetime)| (pixel.pos(), **lifetime))
+            .collect::<Vec<_>>();
+        lit.sort_by_key(|(pos, _)| *pos);
+
+        let cursor = world.query::<&ScanCursor>().single(world);
+
+        (lit, cursor.step, cursor.next_lit_time)
+    }
+
+    /// two seconds of `scan` at 30, 60 and 240 fps
+    fn run_at_frame_rates(scan: Scan) -> [ScanResult; 3] {
+        [
+            run_scan(scan, Duration::from_nanos(33_333_333), 60),
+            run_scan(scan, Duration::from_nanos(16_666_667), 120),
+            run_scan(scan, Duration::from_nanos(4_166_667), 480),
+        ]
+    }
+
+    #[test]
+    fn lit_times_match_at_any_frame_rate() {
+        let [fps_30, fps_60, fps_240] = run_at_frame_rates(Scan {
+            base: 0.4,
+            user_pixel: Some(BellShape::default()),
+            speed: 1.0,
+        });
+
+        // every pixel has been lit, most of them more than once
+        assert!(fps_60.0.iter().all(|&(_, lit_time)| lit_time > 0.0));
+
+        assert_eq!(fps_30, fps_60);
+        assert_eq!(fps_60, fps_240);
+    }
+
+    #[test]
+    fn sweeps_shorter_than_a_frame_keep_their_timing() {
+        // like `scan.easing.ron`, pixels far from the user pixel barely wait at all
+        let scan = Scan {
+            base: 0.0,
+            user_pixel: Some(BellShape {
+                width: 0.2,
+                sharpness: 1.0,
+            }),
+            speed: 20.0,
+        };
+
+        let [fps_30, fps_60, fps_240] = run_at_frame_rates(scan);
+
+        // a whole sweep takes less than a frame at 30 and 60 fps
+        let config = GridConfig {
+            width: 5,
+            height: 3,
+            ..default()
+        };
+        let cursor = ScanCursor::new(0, &CursorSettings::default(), &config, None, 0.0);
+        let mut easing = CombinedBellEasing::new(scan.base);
+        easing.extend(
+            scan.user_pixel
+                .unwrap()
+                .at(cursor.order.normalised(&config.position(3, 1))),
+        );
+        let sweep = GridPosition::all(5, 3)
+            .map(|pos| {
+                PIXEL_WAIT_TIME * easing.evaluate(cursor.order.normalised(&pos)) / scan.speed
+            })
+            .sum::<f64>();
+
+        assert!(sweep > 0.0 && sweep < 1000.0 / 60.0, "{sweep}");
+
+        assert!(fps_30.0.iter().all(|&(_, lit_time)| lit_time > 1900.0));
+        assert_eq!(fps_30, fps_60);
+        assert_eq!(fps_60, fps_240);
+    }
+
+    #[test]
+    fn sweeps_that_take_no_time_do_not_stall() {
+        let scan = Scan {
+            base: 0.0,
+            user_pixel: None,
+            speed: 1.0,
+        };
+
+        for (lit, step, next_lit_time) in run_at_frame_rates(scan) {
+            // every pixel is lit at the time of the last frame, and the cursor waits for the next
+            assert!(
+                lit.iter()
+                    .all(|&(_, lit_time)| (lit_time - 2000.0).abs() < 1e-3),
+                "{lit:?}"
+            );
+            assert_eq!(step, 0);
+            assert!((next_lit_time - 2000.0).abs() < 1e-3, "{next_lit_time}");
+        }
+    }
 }
@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+
+use crate::{
+    grid::{field::InfluenceField, position::GridPosition},
+    scenes::{cursor::ScanCursor, story::CombinedBellEasing},
+};
+
+use super::{systems::scan_easing_at, PIXEL_WAIT_TIME};
+
+/// When each step of a cursor's scan is lit, found by summing `PIXEL_WAIT_TIME * easing(x)`
+/// (divided by the cursor's speed) over its scan order.
+///
+/// Times are in milliseconds and are the ones `update_pixel_lit_time` schedules, so a scan that
+/// waits less than a frame between pixels will run behind them.
+#[derive(Component, Default, Debug)]
+pub struct ScanTimeline {
+    /// `elapsed[i + 1]` is the time from the start of the sweep until step `i` is lit, where the
+    /// wait before step 0 counts as the start of the sweep
+    elapsed: Vec<f64>,
+}
+
+impl ScanTimeline {
+    pub fn new(
+        cursor: &ScanCursor,
+        bell_easing: &CombinedBellEasing,
+        field: &InfluenceField,
+    ) -> Self {
+        let mut elapsed = Vec::with_capacity(cursor.order.len() + 1);
+        let mut total = 0.0;
+
+        elapsed.push(total);
+
+        for step in 0..cursor.order.len() {
+            let pos = GridPosition {
+                packed: cursor.order.packed_at(step),
+                ..cursor.next_lit_pixel
+            };
+
+            total +=
+                PIXEL_WAIT_TIME * scan_easing_at(cursor, bell_easing, field, &pos) / cursor.speed;
+            elapsed.push(total);
+        }
+
+        Self { elapsed }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elapsed.len().saturating_sub(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// how long one full pass over the scan order takes
+    pub fn sweep_duration(&self) -> f64 {
+        self.elapsed.last().copied().unwrap_or_default()
+    }
+
+    /// The wait between lighting the step before `step` and lighting `step`, or `None` if there
+    /// are no steps.
+    pub fn delay_before(&self, step: usize) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let step = step % self.len();
+
+        Some(self.elapsed[step + 1] - self.elapsed[step])
+    }
+
+    /// Time from lighting step `from` until the next time step `to` is lit, or `None` if there
+    /// are no steps. Zero when they are the same step.
+    pub fn time_between(&self, from: usize, to: usize) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let (from, to) = (from % self.len(), to % self.len());
+
+        Some(if to >= from {
+            self.elapsed[to + 1] - self.elapsed[from + 1]
+        } else {
+            self.sweep_duration() - (self.elapsed[from + 1] - self.elapsed[to + 1])
+        })
+    }
+
+    /// Time from `now` until `pos` is next lit, given that `cursor.next_lit_pixel` is lit at
+    /// `cursor.next_lit_time`, or `None` if there are no steps.
+    pub fn time_until(&self, cursor: &ScanCursor, now: f64, pos: &GridPosition) -> Option<f64> {
+        if self.is_empty() || cursor.order.is_empty() {
+            return None;
+        }
+
+        let wait = (cursor.next_lit_time - now).max(0.0);
+
+        self.time_between(cursor.step, cursor.order.step_of(pos))
+            .map(|time| wait + time)
+    }
+
+    /// The most recently lit pixel `time` milliseconds after `now`, or `None` if the scan takes
+    /// no time at all.
+    pub fn position_at(&self, cursor: &ScanCursor, now: f64, time: f64) -> Option<GridPosition> {
+        let sweep = self.sweep_duration();
+
+        if self.is_empty() || sweep <= 0.0 {
+            return None;
+        }
+
+        let remaining = time - (cursor.next_lit_time - now).max(0.0);
+        let len = self.len();
+
+        let step = if remaining < 0.0 {
+            // still waiting on `next_lit_pixel`, so the cursor is on the one before it
+            (cursor.step + len - 1) % len
+        } else {
+            let mut target = self.elapsed[cursor.step + 1] + remaining.rem_euclid(sweep);
+
+            if target >= sweep {
+                target -= sweep;
+            }
+
+            let lit = self.elapsed[1..].partition_point(|&elapsed| elapsed <= target);
+
+            (lit + len - 1) % len
+        };
+
+        Some(GridPosition {
+            packed: cursor.order.packed_at(step),
+            ..cursor.next_lit_pixel
+        })
+    }
+}
+
+/// Recomputes a cursor's `ScanTimeline` whenever the easing or influence field it was built
+/// from changes. Those are rebuilt whenever user pixels, the scan order or the cursor's speed
+/// change, so this covers them too.
+pub(super) fn rebuild_scan_timeline(
+    mut cursors: Query<
+        (
+            &ScanCursor,
+            &CombinedBellEasing,
+            &InfluenceField,
+            &mut ScanTimeline,
+        ),
+        Or<(Changed<CombinedBellEasing>, Changed<InfluenceField>)>,
+    >,
+) {
+    for (cursor, bell_easing, field, mut timeline) in &mut cursors {
+        *timeline = ScanTimeline::new(cursor, bell_easing, field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        easings::{expr::EasingExpr, CombinedEasing},
+        grid::config::GridConfig,
+        scenes::cursor::CursorSettings,
+    };
+
+    use super::*;
+
+    const GRID: GridConfig = GridConfig {
+        width: 4,
+        height: 2,
+        pixel_size: 1.0,
+        gap: 0.0,
+    };
+
+    fn cursor(grid: &GridConfig, speed: f64) -> ScanCursor {
+        let settings = CursorSettings { speed, ..default() };
+
+        ScanCursor::new(0, &settings, grid, None, 0.0)
+    }
+
+    /// a timeline where step `i` of the 8 steps waits `PIXEL_WAIT_TIME * i / 8`
+    fn linear_timeline(cursor: &ScanCursor) -> ScanTimeline {
+        ScanTimeline::new(cursor, &CombinedEasing::new(EasingExpr::Linear), &default())
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn constant_easing_waits_evenly() {
+        let cursor = cursor(&GRID, 2.0);
+        let timeline = ScanTimeline::new(&cursor, &CombinedEasing::new(1.0), &default());
+
+        assert_eq!(timeline.len(), 8);
+        assert_close(timeline.sweep_duration(), 8.0 * PIXEL_WAIT_TIME / 2.0);
+
+        for step in 0..8 {
+            assert_close(timeline.delay_before(step).unwrap(), PIXEL_WAIT_TIME / 2.0);
+        }
+    }
+
+    #[test]
+    fn delays_follow_the_easing() {
+        let cursor = cursor(&GRID, 1.0);
+        let timeline = linear_timeline(&cursor);
+
+        for step in 0..8 {
+            let expected = PIXEL_WAIT_TIME * step as f64 / 8.0;
+
+            assert_close(timeline.delay_before(step).unwrap(), expected);
+            assert_close(timeline.delay_before(step + 8).unwrap(), expected);
+        }
+
+        assert_close(timeline.sweep_duration(), PIXEL_WAIT_TIME * 28.0 / 8.0);
+    }
+
+    #[test]
+    fn time_between_wraps_around() {
+        let cursor = cursor(&GRID, 1.0);
+        let timeline = linear_timeline(&cursor);
+        let step = PIXEL_WAIT_TIME / 8.0;
+
+        assert_eq!(timeline.time_between(3, 3), Some(0.0));
+        assert_close(timeline.time_between(1, 3).unwrap(), step * (2.0 + 3.0));
+
+        // 6 -> 7 -> 0 -> 1
+        assert_close(
+            timeline.time_between(6, 1).unwrap(),
+            step * (7.0 + 0.0 + 1.0),
+        );
+
+        for (from, to) in [(0, 5), (2, 7), (5, 2), (7, 0)] {
+            let there = timeline.time_between(from, to).unwrap();
+            let back = timeline.time_between(to, from).unwrap();
+
+            assert_close(there + back, timeline.sweep_duration());
+        }
+    }
+
+    #[test]
+    fn time_until_adds_the_wait() {
+        let mut cursor = cursor(&GRID, 1.0);
+        let timeline = linear_timeline(&cursor);
+
+        cursor.next_lit_time = 100.0;
+
+        let pos = GRID.position(2, 0);
+        let between = timeline.time_between(0, 2).unwrap();
+
+        assert_close(
+            timeline.time_until(&cursor, 40.0, &pos).unwrap(),
+            60.0 + between,
+        );
+        assert_close(timeline.time_until(&cursor, 140.0, &pos).unwrap(), between);
+    }
+
+    #[test]
+    fn position_at_walks_the_scan() {
+        let mut cursor = cursor(&GRID, 1.0);
+        let timeline = ScanTimeline::new(&cursor, &CombinedEasing::new(1.0), &default());
+
+        cursor.next_lit_time = 10.0;
+
+        // still waiting on step 0, so the cursor is on the last step
+        let pos = timeline.position_at(&cursor, 0.0, 5.0).unwrap();
+        assert_eq!(pos.packed, 7);
+
+        let pos = timeline.position_at(&cursor, 0.0, 10.0).unwrap();
+        assert_eq!(pos.packed, 0);
+
+        let pos = timeline
+            .position_at(&cursor, 0.0, 10.0 + 2.5 * PIXEL_WAIT_TIME)
+            .unwrap();
+        assert_eq!(pos.packed, 2);
+
+        let sweep = timeline.sweep_duration();
+        let pos = timeline.position_at(&cursor, 0.0, 10.0 + sweep).unwrap();
+        assert_eq!(pos.packed, 0);
+    }
+
+    #[test]
+    fn empty_timeline_has_no_timing() {
+        let empty = GridConfig { width: 0, ..GRID };
+        let cursor = cursor(&empty, 1.0);
+        let timeline = ScanTimeline::new(&cursor, &CombinedEasing::new(1.0), &default());
+
+        assert!(timeline.is_empty());
+        assert_eq!(timeline.delay_before(3), None);
+        assert_eq!(timeline.time_between(1, 2), None);
+        assert_eq!(
+            timeline.time_until(&cursor, 0.0, &GRID.position(0, 0)),
+            None
+        );
+        assert_eq!(timeline.position_at(&cursor, 0.0, 10.0), None);
+
+        assert_eq!(ScanTimeline::default().delay_before(0), None);
+    }
+}
@@ -0,0 +1,448 @@
+use bevy::prelude::*;
+
+use crate::{
+    grid::position::GridPosition, materials::pixel_instances::PixelInstance,
+    scenes::story::LevelSettings,
+};
+
+use super::{components::UserPixelMarker, index::PixelGrid, USER_PIXEL_OUTLINE_THICKNESS};
+
+/// Every user pixel, oldest first. Kept up to date by the observers on [`UserPixelMarker`], so it
+/// also covers markers inserted directly rather than through [`UserPixelCommands`].
+#[derive(Resource, Default, Debug)]
+pub struct UserPixels {
+    entities: Vec<Entity>,
+}
+
+impl UserPixels {
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    /// the user pixel that was placed first
+    pub fn oldest(&self) -> Option<Entity> {
+        self.entities.first().copied()
+    }
+
+    /// the user pixel that was placed last
+    pub fn newest(&self) -> Option<Entity> {
+        self.entities.last().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().copied()
+    }
+}
+
+/// Sent when a user pixel moves to another cell, by [`UserPixelCommands::move_user_pixel`] or
+/// [`UserPixelCommands::swap_user_pixels`].
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UserPixelMoved {
+    /// the pixel the user pixel is now on
+    pub entity: Entity,
+    pub from: GridPosition,
+    pub to: GridPosition,
+}
+
+/// Places, moves and removes user pixels by grid position.
+pub trait UserPixelCommands {
+    /// Makes the pixel at `pos` a user pixel. If that goes over `LevelSettings::max_user_pixels`
+    /// the oldest user pixel is removed.
+    fn add_user_pixel(&mut self, pos: GridPosition, marker: UserPixelMarker);
+
+    /// Moves the user pixel at `from` to `to`. If `to` is already a user pixel the two swap places.
+    fn move_user_pixel(&mut self, from: GridPosition, to: GridPosition);
+
+    /// Swaps whatever is at `a` and `b`, so a user pixel on either moves to the other.
+    fn swap_user_pixels(&mut self, a: GridPosition, b: GridPosition);
+
+    /// Turns the user pixel at `pos` back into a regular pixel.
+    fn remove_user_pixel(&mut self, pos: GridPosition);
+}
+
+impl UserPixelCommands for Commands<'_, '_> {
+    fn add_user_pixel(&mut self, pos: GridPosition, marker: UserPixelMarker) {
+        self.queue(move |world: &mut World| {
+            let Some(entity) = world.resource::<PixelGrid>().get(&pos) else {
+                warn!("unable to set pixel to user pixel: {}", pos.packed);
+                return;
+            };
+
+            world.entity_mut(entity).insert(marker);
+        });
+    }
+
+    fn move_user_pixel(&mut self, from: GridPosition, to: GridPosition) {
+        self.queue(move |world: &mut World| {
+            let Some(entity) = world.resource::<PixelGrid>().get(&from) else {
+                warn!("unable to move user pixel from: {}", from.packed);
+                return;
+            };
+
+            if !world.entity(entity).contains::<UserPixelMarker>() {
+                warn!("no user pixel to move at: {}", from.packed);
+                return;
+            }
+
+            swap_user_pixels(world, from, to);
+        });
+    }
+
+    fn swap_user_pixels(&mut self, a: GridPosition, b: GridPosition) {
+        self.queue(move |world: &mut World| swap_user_pixels(world, a, b));
+    }
+
+    fn remove_user_pixel(&mut self, pos: GridPosition) {
+        self.queue(move |world: &mut World| {
+            let Some(entity) = world.resource::<PixelGrid>().get(&pos) else {
+                warn!("unable to remove user pixel: {}", pos.packed);
+                return;
+            };
+
+            world.entity_mut(entity).remove::<UserPixelMarker>();
+        });
+    }
+}
+
+fn swap_user_pixels(world: &mut World, a: GridPosition, b: GridPosition) {
+    let pixels = world.resource::<PixelGrid>();
+
+    let (Some(a_entity), Some(b_entity)) = (pixels.get(&a), pixels.get(&b)) else {
+        warn!("unable to swap user pixels: {} and {}", a.packed, b.packed);
+        return;
+    };
+
+    if a_entity == b_entity {
+        return;
+    }
+
+    let a_marker = world.get::<UserPixelMarker>(a_entity).copied();
+    let b_marker = world.get::<UserPixelMarker>(b_entity).copied();
+    let slots = world.resource::<UserPixels>().entities.clone();
+
+    let swapped = [(a_entity, b_marker), (b_entity, a_marker)];
+
+    // removing first, so moving a user pixel never briefly goes over the limit and evicts one
+    for (entity, marker) in swapped {
+        if marker.is_none() {
+            world.entity_mut(entity).remove::<UserPixelMarker>();
+        }
+    }
+
+    for (entity, marker) in swapped {
+        if let Some(marker) = marker {
+            world.entity_mut(entity).insert(marker);
+        }
+    }
+
+    // The observers put a user pixel moved onto a regular pixel at the back of `UserPixels` as if
+    // it were new, and leave two swapped user pixels where they were. Either way each one should
+    // keep its place in the eviction order, so it takes over the slot of the pixel it came from.
+    let moved = |entity| match entity {
+        entity if entity == a_entity => b_entity,
+        entity if entity == b_entity => a_entity,
+        entity => entity,
+    };
+
+    world.resource_mut::<UserPixels>().entities = slots.into_iter().map(moved).collect();
+
+    if a_marker.is_some() {
+        world.send_event(UserPixelMoved {
+            entity: b_entity,
+            from: a,
+            to: b,
+        });
+    }
+
+    if b_marker.is_some() {
+        world.send_event(UserPixelMoved {
+            entity: a_entity,
+            from: b,
+            to: a,
+        });
+    }
+}
+
+/// Outlines new user pixels and removes the oldest ones over `LevelSettings::max_user_pixels`.
+pub(super) fn user_pixel_added_observer(
+    trigger: Trigger<OnAdd, UserPixelMarker>,
+    mut commands: Commands,
+    level: Res<LevelSettings>,
+    mut user_pixels: ResMut<UserPixels>,
+    mut query: Query<&mut PixelInstance, With<UserPixelMarker>>,
+) {
+    let entity = trigger.entity();
+
+    if let Ok(mut instance) = query.get_mut(entity) {
+        instance.outline_thickness = USER_PIXEL_OUTLINE_THICKNESS;
+    }
+
+    user_pixels.entities.push(entity);
+
+    let excess = user_pixels.len().saturating_sub(level.max_user_pixels);
+
+    for oldest in user_pixels.entities.drain(..excess) {
+        commands.entity(oldest).remove::<UserPixelMarker>();
+    }
+}
+
+/// Takes the outline back off of pixels that stop being user pixels.
+pub(super) fn user_pixel_removed_observer(
+    trigger: Trigger<OnRemove, UserPixelMarker>,
+    mut user_pixels: ResMut<UserPixels>,
+    mut query: Query<&mut PixelInstance>,
+) {
+    let entity = trigger.entity();
+
+    if let Ok(mut instance) = query.get_mut(entity) {
+        instance.outline_thickness = 0.0;
+    }
+
+    user_pixels
+        .entities
+        .retain(|&user_pixel| user_pixel != entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{easings::expr::BellShape, pixels::components::Pixel};
+
+    use super::*;
+
+    const WIDTH: i32 = 4;
+    const HEIGHT: i32 = 3;
+
+    fn pos(x: i32, y: i32) -> GridPosition {
+        GridPosition::new(WIDTH, HEIGHT, x, y)
+    }
+
+    fn app(max_user_pixels: usize) -> App {
+        let mut app = App::new();
+
+        app.init_resource::<PixelGrid>();
+        app.init_resource::<UserPixels>();
+        app.insert_resource(LevelSettings {
+            max_user_pixels,
+            ..default()
+        });
+        app.add_event::<UserPixelMoved>();
+        app.add_observer(user_pixel_added_observer);
+        app.add_observer(user_pixel_removed_observer);
+
+        let world = app.world_mut();
+
+        for pos in GridPosition::all(WIDTH, HEIGHT) {
+            world.spawn(Pixel::new(pos));
+        }
+
+        app
+    }
+
+    fn run(app: &mut App, commands: impl FnOnce(&mut Commands)) {
+        let world = app.world_mut();
+
+        commands(&mut world.commands());
+        world.flush();
+    }
+
+    fn entity(app: &App, pos: GridPosition) -> Entity {
+        app.world().resource::<PixelGrid>().get(&pos).unwrap()
+    }
+
+    fn user_pixels(app: &App) -> Vec<Entity> {
+        app.world().resource::<UserPixels>().iter().collect()
+    }
+
+    fn outline(app: &App, pos: GridPosition) -> f32 {
+        app.world()
+            .get::<PixelInstance>(entity(app, pos))
+            .unwrap()
+            .outline_thickness
+    }
+
+    fn moved(app: &App) -> Vec<(Entity, GridPosition, GridPosition)> {
+        app.world()
+            .resource::<Events<UserPixelMoved>>()
+            .iter_current_update_events()
+            .map(|moved| (moved.entity, moved.from, moved.to))
+            .collect()
+    }
+
+    fn marker(width: f64) -> UserPixelMarker {
+        UserPixelMarker {
+            bell: Some(BellShape { width, ..default() }),
+        }
+    }
+
+    #[test]
+    fn adding_outlines_user_pixels() {
+        let mut app = app(8);
+
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(1, 1), default());
+            commands.add_user_pixel(pos(2, 0), default());
+        });
+
+        assert_eq!(
+            user_pixels(&app),
+            [entity(&app, pos(1, 1)), entity(&app, pos(2, 0))]
+        );
+        assert_eq!(outline(&app, pos(1, 1)), USER_PIXEL_OUTLINE_THICKNESS);
+        assert_eq!(outline(&app, pos(2, 0)), USER_PIXEL_OUTLINE_THICKNESS);
+        assert_eq!(outline(&app, pos(0, 0)), 0.0);
+    }
+
+    #[test]
+    fn adding_past_the_limit_evicts_the_oldest() {
+        let mut app = app(2);
+
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(0, 0), default());
+            commands.add_user_pixel(pos(1, 0), default());
+            commands.add_user_pixel(pos(2, 0), default());
+        });
+
+        assert_eq!(
+            user_pixels(&app),
+            [entity(&app, pos(1, 0)), entity(&app, pos(2, 0))]
+        );
+
+        let oldest = entity(&app, pos(0, 0));
+        assert!(!app.world().entity(oldest).contains::<UserPixelMarker>());
+        assert_eq!(outline(&app, pos(0, 0)), 0.0);
+    }
+
+    #[test]
+    fn no_user_pixels_allowed() {
+        let mut app = app(0);
+
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(1, 1), default());
+        });
+
+        assert!(user_pixels(&app).is_empty());
+        assert!(!app
+            .world()
+            .entity(entity(&app, pos(1, 1)))
+            .contains::<UserPixelMarker>());
+        assert_eq!(outline(&app, pos(1, 1)), 0.0);
+    }
+
+    #[test]
+    fn moving_sends_an_event() {
+        let mut app = app(1);
+
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(0, 0), marker(0.1));
+        });
+        run(&mut app, |commands| {
+            commands.move_user_pixel(pos(0, 0), pos(3, 2));
+        });
+
+        let to = entity(&app, pos(3, 2));
+
+        // moving at the limit doesn't evict the pixel being moved
+        assert_eq!(user_pixels(&app), [to]);
+        assert_eq!(app.world().get::<UserPixelMarker>(to), Some(&marker(0.1)));
+        assert_eq!(outline(&app, pos(0, 0)), 0.0);
+        assert_eq!(outline(&app, pos(3, 2)), USER_PIXEL_OUTLINE_THICKNESS);
+
+        assert_eq!(moved(&app), [(to, pos(0, 0), pos(3, 2))]);
+    }
+
+    #[test]
+    fn moving_keeps_the_eviction_order() {
+        let mut app = app(2);
+
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(0, 0), marker(0.1));
+            commands.add_user_pixel(pos(1, 0), marker(0.2));
+        });
+        run(&mut app, |commands| {
+            commands.move_user_pixel(pos(0, 0), pos(3, 2));
+        });
+
+        assert_eq!(
+            user_pixels(&app),
+            [entity(&app, pos(3, 2)), entity(&app, pos(1, 0))]
+        );
+
+        // the moved pixel is still the oldest, so it goes first
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(2, 0), marker(0.3));
+        });
+
+        assert_eq!(
+            user_pixels(&app),
+            [entity(&app, pos(1, 0)), entity(&app, pos(2, 0))]
+        );
+        assert!(!app
+            .world()
+            .entity(entity(&app, pos(3, 2)))
+            .contains::<UserPixelMarker>());
+        assert_eq!(
+            app.world().get::<UserPixelMarker>(entity(&app, pos(1, 0))),
+            Some(&marker(0.2))
+        );
+    }
+
+    #[test]
+    fn moving_without_a_user_pixel_does_nothing() {
+        let mut app = app(8);
+
+        run(&mut app, |commands| {
+            commands.move_user_pixel(pos(0, 0), pos(1, 0));
+        });
+
+        assert!(user_pixels(&app).is_empty());
+        assert!(moved(&app).is_empty());
+    }
+
+    #[test]
+    fn swapping_keeps_the_order() {
+        let mut app = app(2);
+
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(0, 0), marker(0.1));
+            commands.add_user_pixel(pos(1, 0), marker(0.2));
+        });
+        run(&mut app, |commands| {
+            commands.swap_user_pixels(pos(0, 0), pos(1, 0));
+        });
+
+        let (a, b) = (entity(&app, pos(0, 0)), entity(&app, pos(1, 0)));
+
+        // the first user pixel placed is still the oldest, now on `b`
+        assert_eq!(user_pixels(&app), [b, a]);
+        assert_eq!(app.world().get::<UserPixelMarker>(a), Some(&marker(0.2)));
+        assert_eq!(app.world().get::<UserPixelMarker>(b), Some(&marker(0.1)));
+
+        assert_eq!(
+            moved(&app),
+            [(b, pos(0, 0), pos(1, 0)), (a, pos(1, 0), pos(0, 0))]
+        );
+    }
+
+    #[test]
+    fn removing_takes_the_outline_off() {
+        let mut app = app(8);
+
+        run(&mut app, |commands| {
+            commands.add_user_pixel(pos(2, 1), default());
+        });
+        run(&mut app, |commands| {
+            commands.remove_user_pixel(pos(2, 1));
+        });
+
+        assert!(user_pixels(&app).is_empty());
+        assert_eq!(outline(&app, pos(2, 1)), 0.0);
+    }
+}
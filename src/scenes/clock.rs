@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+/// slowest the game clock can run, relative to real time
+pub const MIN_CLOCK_SPEED: f64 = 0.1;
+/// fastest the game clock can run, relative to real time
+pub const MAX_CLOCK_SPEED: f64 = 4.0;
+
+/// how far a single step moves a paused game clock, before the clock speed is applied
+pub const CLOCK_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Moves a paused game clock forward by one [`CLOCK_STEP`].
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct StepClock;
+
+/// Milliseconds the game clock has run for. The scanline, pixel fading and easing timing all read
+/// this rather than real time, so they stop while the game is paused.
+#[inline]
+pub fn game_millis(time: &Time<Virtual>) -> f64 {
+    time.elapsed_secs_f64() * 1000.0
+}
+
+/// Sets the game clock speed, clamped to [`MIN_CLOCK_SPEED`]..=[`MAX_CLOCK_SPEED`].
+pub fn set_clock_speed(time: &mut Time<Virtual>, speed: f64) {
+    // `clamp` passes NaN on
+    let speed = if speed.is_nan() {
+        MIN_CLOCK_SPEED
+    } else {
+        speed
+    };
+
+    time.set_relative_speed_f64(speed.clamp(MIN_CLOCK_SPEED, MAX_CLOCK_SPEED));
+}
+
+pub(super) fn pause_clock(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+pub(super) fn resume_clock(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+/// Runs before `Update`, so everything in the frame sees the stepped clock.
+pub(super) fn step_clock(mut steps: EventReader<StepClock>, mut time: ResMut<Time<Virtual>>) {
+    for _ in steps.read() {
+        let step = CLOCK_STEP.mul_f64(time.relative_speed_f64());
+
+        time.advance_by(step);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        state::app::StatesPlugin,
+        time::{TimePlugin, TimeUpdateStrategy},
+    };
+
+    use crate::scenes::{PauseState, SceneState};
+
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(20);
+
+    /// a game scene with the clock systems from the scenes plugin, running
+    fn app() -> App {
+        let mut app = App::new();
+
+        app.add_plugins((TimePlugin, StatesPlugin));
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(FRAME));
+        app.init_state::<SceneState>();
+        app.add_sub_state::<PauseState>();
+        app.add_event::<StepClock>();
+        app.add_systems(OnEnter(PauseState::Paused), pause_clock);
+        app.add_systems(OnExit(PauseState::Paused), resume_clock);
+        app.add_systems(PreUpdate, step_clock.run_if(in_state(PauseState::Paused)));
+
+        app.world_mut()
+            .resource_mut::<NextState<SceneState>>()
+            .set(SceneState::Game);
+        app.update();
+
+        app
+    }
+
+    /// runs a frame `FRAME` of real time after the last one, returning the game time after it
+    fn frame(app: &mut App) -> Duration {
+        app.update();
+
+        app.world().resource::<Time<Virtual>>().elapsed()
+    }
+
+    fn set_paused(app: &mut App, paused: bool) {
+        app.world_mut()
+            .resource_mut::<NextState<PauseState>>()
+            .set(if paused {
+                PauseState::Paused
+            } else {
+                PauseState::Running
+            });
+    }
+
+    #[test]
+    fn pausing_freezes_the_clock() {
+        let mut app = app();
+
+        let running = frame(&mut app);
+        assert_eq!(frame(&mut app), running + FRAME);
+
+        set_paused(&mut app, true);
+        let paused = frame(&mut app);
+
+        for _ in 0..5 {
+            assert_eq!(frame(&mut app), paused);
+        }
+
+        // the clock moves on in `First`, before the state changes
+        set_paused(&mut app, false);
+        assert_eq!(frame(&mut app), paused);
+        assert_eq!(frame(&mut app), paused + FRAME);
+    }
+
+    #[test]
+    fn steps_advance_a_paused_clock_by_one_step() {
+        for speed in [1.0, 0.5, 2.0] {
+            let mut app = app();
+            set_clock_speed(&mut app.world_mut().resource_mut::<Time<Virtual>>(), speed);
+
+            set_paused(&mut app, true);
+            let paused = frame(&mut app);
+
+            app.world_mut().send_event(StepClock);
+            let stepped = frame(&mut app);
+            assert_eq!(stepped, paused + CLOCK_STEP.mul_f64(speed), "x{speed}");
+
+            // only for the frame it was sent on
+            assert_eq!(frame(&mut app), stepped, "x{speed}");
+
+            app.world_mut().send_event(StepClock);
+            app.world_mut().send_event(StepClock);
+            assert_eq!(
+                frame(&mut app),
+                stepped + 2 * CLOCK_STEP.mul_f64(speed),
+                "x{speed}"
+            );
+        }
+    }
+
+    #[test]
+    fn steps_do_nothing_while_running() {
+        let mut app = app();
+        let running = frame(&mut app);
+
+        app.world_mut().send_event(StepClock);
+        assert_eq!(frame(&mut app), running + FRAME);
+    }
+
+    #[test]
+    fn speed_is_clamped() {
+        let mut time = Time::<Virtual>::default();
+
+        for (speed, clamped) in [
+            (1.0, 1.0),
+            (0.0, MIN_CLOCK_SPEED),
+            (-2.0, MIN_CLOCK_SPEED),
+            (MIN_CLOCK_SPEED / 2.0, MIN_CLOCK_SPEED),
+            (f64::NAN, MIN_CLOCK_SPEED),
+            (MAX_CLOCK_SPEED * 2.0, MAX_CLOCK_SPEED),
+            (f64::INFINITY, MAX_CLOCK_SPEED),
+        ] {
+            set_clock_speed(&mut time, speed);
+
+            assert_eq!(time.relative_speed_f64(), clamped, "{speed}");
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use bevy::prelude::*;
+
+use crate::{
+    easings::CombinedEasing,
+    grid::{
+        config::GridConfig,
+        field::InfluenceField,
+        mask::GridMask,
+        position::GridPosition,
+        scan_pattern::{ScanOrder, ScanPattern, ScanPatternKind},
+    },
+    pixels::timeline::ScanTimeline,
+};
+
+use super::{
+    clock::game_millis,
+    story::{LevelSettings, PixelStates, ScanEasing, SCAN_EASING_PATH},
+    SceneState,
+};
+
+/// slowest a cursor can move, relative to its normal speed
+pub const MIN_CURSOR_SPEED: f64 = 0.01;
+
+/// Settings for one of the level's scanline cursors.
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub struct CursorSettings {
+    pub scan_pattern: ScanPatternKind,
+    /// how many times faster than normal the cursor moves between pixels, at least
+    /// [`MIN_CURSOR_SPEED`]
+    pub speed: f64,
+    /// asset path of the easing the cursor's user pixel bells are combined with
+    pub easing: String,
+    /// tint of the pixels this cursor lights
+    pub color: LinearRgba,
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self {
+            scan_pattern: ScanPatternKind::default(),
+            speed: 1.0,
+            easing: SCAN_EASING_PATH.to_string(),
+            color: LinearRgba::WHITE,
+        }
+    }
+}
+
+/// How the lights that different cursors left on the same pixel are combined.
+#[derive(Reflect, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LightCombine {
+    /// only the most recent light shows
+    #[default]
+    Latest,
+    /// the brightest light shows
+    Max,
+    /// lights add up, with their tints blended by brightness
+    Add,
+}
+
+/// Clamps a cursor speed to at least [`MIN_CURSOR_SPEED`], so the scan can't stall or run
+/// backwards.
+pub fn clamp_cursor_speed(speed: f64) -> f64 {
+    // `max` also turns NaN into the minimum
+    speed.max(MIN_CURSOR_SPEED)
+}
+
+impl LightCombine {
+    /// Combines `(brightness, tint, lit time)` lights into a single brightness and tint.
+    pub fn apply(
+        self,
+        lights: impl IntoIterator<Item = (f64, LinearRgba, f64)>,
+    ) -> Option<(f64, LinearRgba)> {
+        let lights = lights.into_iter();
+
+        match self {
+            Self::Latest => lights
+                .max_by(|a, b| a.2.total_cmp(&b.2))
+                .map(|(brightness, tint, _)| (brightness, tint)),
+            Self::Max => lights
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(brightness, tint, _)| (brightness, tint)),
+            Self::Add => {
+                let (total, tint) = lights.fold(
+                    (0.0, LinearRgba::NONE),
+                    |(total, tint), (brightness, light_tint, _)| {
+                        (total + brightness, tint + light_tint * brightness as f32)
+                    },
+                );
+
+                (total > 0.0).then(|| (total.min(1.0), tint * (1.0 / total as f32)))
+            }
+        }
+    }
+}
+
+/// A scanline moving over the grid, lighting one pixel at a time.
+///
+/// Every cursor entity also carries its own [`ScanEasing`], [`CombinedEasing`],
+/// [`InfluenceField`] and [`ScanTimeline`].
+#[derive(Component, Reflect)]
+pub struct ScanCursor {
+    /// position of the cursor's settings in `LevelSettings::cursors`
+    pub index: usize,
+    pub next_lit_pixel: GridPosition,
+    pub next_lit_time: f64,
+    /// how many pixels into the scan order `next_lit_pixel` is
+    pub step: usize,
+    pub order: ScanOrder,
+    pub scan_pattern: ScanPatternKind,
+    pub speed: f64,
+    pub color: LinearRgba,
+}
+
+impl ScanCursor {
+    /// A cursor at the start of its scan, lighting its first pixel at `start` (in milliseconds of
+    /// elapsed time).
+    pub fn new(
+        index: usize,
+        settings: &CursorSettings,
+        grid: &GridConfig,
+        mask: Option<&GridMask>,
+        start: f64,
+    ) -> Self {
+        let mut cursor = Self {
+            index,
+            next_lit_pixel: grid.position(0, 0),
+            next_lit_time: start,
+            step: 0,
+            order: Self::scan_order(grid, mask, &settings.scan_pattern),
+            scan_pattern: settings.scan_pattern,
+            speed: clamp_cursor_speed(settings.speed),
+            color: settings.color,
+        };
+
+        cursor.set_step(0);
+        cursor
+    }
+
+    fn scan_order(
+        grid: &GridConfig,
+        mask: Option<&GridMask>,
+        pattern: &(impl ScanPattern + ?Sized),
+    ) -> ScanOrder {
+        match mask {
+            Some(mask) => ScanOrder::masked(pattern, grid.width, grid.height, mask),
+            None => ScanOrder::new(pattern, grid.width, grid.height),
+        }
+    }
+
+    fn set_step(&mut self, step: usize) {
+        // an empty grid has nothing to scan, so the cursor stays where it is
+        if self.order.is_empty() {
+            self.step = 0;
+            return;
+        }
+
+        self.step = step % self.order.len();
+        self.next_lit_pixel = GridPosition {
+            packed: self.order.packed_at(self.step),
+            ..self.next_lit_pixel
+        };
+    }
+
+    #[inline]
+    pub fn update_next_pixel(&mut self) {
+        self.set_step(self.step + 1);
+    }
+
+    /// progress of `next_lit_pixel` through the current scan, in [0, 1)
+    pub fn progress(&self) -> f64 {
+        self.order.normalised(&self.next_lit_pixel)
+    }
+
+    /// Re-evaluates the scan order for `grid` and `mask`, keeping the cursor on the same cell
+    /// where possible. If that cell is masked out the cursor moves on to the next one. When
+    /// nothing is left to scan the cursor goes back to the first cell of `grid`.
+    pub fn reorder(&mut self, grid: &GridConfig, mask: Option<&GridMask>) {
+        self.order = Self::scan_order(grid, mask, &self.scan_pattern);
+
+        if self.order.is_empty() {
+            self.next_lit_pixel = grid.position(0, 0);
+            self.set_step(0);
+            return;
+        }
+
+        let cursor = grid.remap(self.next_lit_pixel);
+        self.next_lit_pixel = cursor;
+
+        self.set_step(self.order.step_of(&cursor));
+    }
+}
+
+pub fn spawn_scan_cursor(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    index: usize,
+    settings: &CursorSettings,
+    state: &PixelStates,
+    start: f64,
+) {
+    commands.spawn((
+        StateScoped(SceneState::Game),
+        ScanCursor::new(index, settings, &state.grid, state.mask.as_ref(), start),
+        ScanEasing(asset_server.load(&settings.easing)),
+        CombinedEasing::new(0.0),
+        InfluenceField::default(),
+        ScanTimeline::default(),
+    ));
+}
+
+/// Brings the cursor entities in line with `LevelSettings::cursors`, keeping the position of
+/// cursors that are still around.
+pub(crate) fn sync_scan_cursors(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    level: Res<LevelSettings>,
+    state: Res<PixelStates>,
+    asset_server: Res<AssetServer>,
+    mut cursors: Query<(Entity, &mut ScanCursor, &mut ScanEasing)>,
+) {
+    let mut existing = vec![false; level.cursors.len()];
+
+    for (entity, mut cursor, mut scan_easing) in &mut cursors {
+        let Some(settings) = level.cursors.get(cursor.index) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        existing[cursor.index] = true;
+
+        cursor.speed = clamp_cursor_speed(settings.speed);
+        cursor.color = settings.color;
+
+        if cursor.scan_pattern != settings.scan_pattern {
+            cursor.scan_pattern = settings.scan_pattern;
+            cursor.reorder(&state.grid, state.mask.as_ref());
+        }
+
+        let path = scan_easing.0.path().map(|path| path.to_string());
+
+        if path.as_deref() != Some(settings.easing.as_str()) {
+            scan_easing.0 = asset_server.load(&settings.easing);
+        }
+    }
+
+    let now = game_millis(&time);
+
+    for (index, settings) in level.cursors.iter().enumerate() {
+        if !existing[index] {
+            spawn_scan_cursor(&mut commands, &asset_server, index, settings, &state, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_grid_does_not_panic() {
+        let empty = GridConfig {
+            width: 0,
+            height: 3,
+            ..default()
+        };
+
+        let mut cursor = ScanCursor::new(0, &CursorSettings::default(), &empty, None, 0.0);
+
+        cursor.update_next_pixel();
+        cursor.reorder(&empty, None);
+
+        assert_eq!(cursor.step, 0);
+        assert!(cursor.order.is_empty());
+    }
+
+    #[test]
+    fn empty_order_resets_the_cursor_to_the_new_grid() {
+        let grid = GridConfig {
+            width: 5,
+            height: 3,
+            ..default()
+        };
+        let empty = GridConfig {
+            width: 0,
+            height: 2,
+            ..default()
+        };
+        let smaller = GridConfig {
+            width: 2,
+            height: 2,
+            ..default()
+        };
+
+        let mut cursor = ScanCursor::new(0, &CursorSettings::default(), &grid, None, 0.0);
+
+        for _ in 0..14 {
+            cursor.update_next_pixel();
+        }
+
+        assert_eq!(cursor.next_lit_pixel, grid.position(4, 2));
+
+        cursor.reorder(&empty, None);
+
+        assert!(cursor.order.is_empty());
+        assert_eq!(cursor.step, 0);
+        assert_eq!(cursor.next_lit_pixel, empty.position(0, 0));
+
+        cursor.update_next_pixel();
+        assert_eq!(cursor.next_lit_pixel, empty.position(0, 0));
+
+        // picks up from the start, rather than from wherever it was on the 5x3 grid
+        cursor.reorder(&smaller, None);
+
+        assert_eq!(cursor.step, 0);
+        assert_eq!(cursor.next_lit_pixel, smaller.position(0, 0));
+
+        cursor.update_next_pixel();
+        assert_eq!(cursor.next_lit_pixel, smaller.position(1, 0));
+    }
+
+    #[test]
+    fn steps_wrap_around_the_scan() {
+        let grid = GridConfig {
+            width: 3,
+            height: 2,
+            ..default()
+        };
+
+        let mut cursor = ScanCursor::new(0, &CursorSettings::default(), &grid, None, 0.0);
+
+        for _ in 0..7 {
+            cursor.update_next_pixel();
+        }
+
+        assert_eq!(cursor.step, 1);
+        assert_eq!(cursor.next_lit_pixel.packed, 1);
+    }
+
+    #[test]
+    fn speed_is_clamped() {
+        let grid = GridConfig::default();
+
+        for (speed, expected) in [
+            (2.0, 2.0),
+            (0.0, MIN_CURSOR_SPEED),
+            (-1.0, MIN_CURSOR_SPEED),
+            (f64::NAN, MIN_CURSOR_SPEED),
+        ] {
+            let settings = CursorSettings { speed, ..default() };
+
+            assert_eq!(
+                ScanCursor::new(0, &settings, &grid, None, 0.0).speed,
+                expected
+            );
+        }
+    }
+
+    const RED: LinearRgba = LinearRgba::RED;
+    const BLUE: LinearRgba = LinearRgba::BLUE;
+
+    #[test]
+    fn light_combine_without_lights() {
+        for combine in [LightCombine::Latest, LightCombine::Max, LightCombine::Add] {
+            assert_eq!(combine.apply([]), None);
+        }
+    }
+
+    #[test]
+    fn light_combine_latest() {
+        let lights = [(0.8, RED, 10.0), (0.2, BLUE, 20.0)];
+
+        assert_eq!(LightCombine::Latest.apply(lights), Some((0.2, BLUE)));
+    }
+
+    #[test]
+    fn light_combine_max() {
+        let lights = [(0.8, RED, 10.0), (0.2, BLUE, 20.0)];
+
+        assert_eq!(LightCombine::Max.apply(lights), Some((0.8, RED)));
+    }
+
+    #[test]
+    fn light_combine_add() {
+        let (brightness, tint) = LightCombine::Add
+            .apply([(0.3, RED, 10.0), (0.1, BLUE, 20.0)])
+            .unwrap();
+
+        assert!((brightness - 0.4).abs() < 1e-12);
+        assert!((tint.red - 0.75).abs() < 1e-6);
+        assert!((tint.blue - 0.25).abs() < 1e-6);
+
+        // brightness is capped at 1 but tints are still weighted by the uncapped total
+        let (brightness, tint) = LightCombine::Add
+            .apply([(0.9, RED, 10.0), (0.9, BLUE, 20.0)])
+            .unwrap();
+
+        assert_eq!(brightness, 1.0);
+        assert!((tint.red - 0.5).abs() < 1e-6);
+        assert!((tint.blue - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn light_combine_add_without_brightness() {
+        assert_eq!(LightCombine::Add.apply([(0.0, RED, 10.0)]), None);
+    }
+}
@@ -1,7 +1,15 @@
+pub mod clock;
+pub mod cursor;
 pub mod story;
+pub mod sweep;
 
 use bevy::prelude::*;
-use story::setup_game_scene;
+use clock::{pause_clock, resume_clock, step_clock, StepClock};
+use cursor::{sync_scan_cursors, ScanCursor};
+use story::{
+    apply_level_mask, load_level_mask, setup_game_scene, LevelMask, LevelSettings, PixelStates,
+};
+use sweep::{CursorPassedUserPixel, RowStarted, ScanSweeps, SweepCompleted, SweepStarted};
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum SceneState {
@@ -10,8 +18,49 @@ pub enum SceneState {
     Game,
 }
 
+/// Whether the game clock is running. Pausing only freezes the game clock, so the debug UI and
+/// menus keep running.
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[source(SceneState = SceneState::Game)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
 pub fn plugin(app: &mut App) {
     app.init_state::<SceneState>();
     app.enable_state_scoped_entities::<SceneState>();
+    app.add_sub_state::<PauseState>();
+    app.add_event::<StepClock>();
+    app.register_type::<LevelSettings>();
+    app.init_resource::<LevelSettings>();
+    app.register_type::<ScanCursor>();
+    app.register_type::<ScanSweeps>();
+    app.init_resource::<ScanSweeps>();
+    app.add_event::<SweepStarted>();
+    app.add_event::<SweepCompleted>();
+    app.add_event::<RowStarted>();
+    app.add_event::<CursorPassedUserPixel>();
+
     app.add_systems(OnEnter(SceneState::Game), setup_game_scene);
+    app.add_systems(OnEnter(PauseState::Paused), pause_clock);
+    app.add_systems(OnExit(PauseState::Paused), resume_clock);
+    app.add_systems(PreUpdate, step_clock.run_if(in_state(PauseState::Paused)));
+    app.add_systems(
+        Update,
+        sync_scan_cursors.run_if(
+            in_state(SceneState::Game)
+                .and(resource_exists::<PixelStates>)
+                .and(resource_changed::<LevelSettings>),
+        ),
+    );
+    app.add_systems(
+        Update,
+        (
+            load_level_mask.run_if(resource_changed::<LevelSettings>),
+            apply_level_mask.run_if(resource_exists::<LevelMask>),
+        )
+            .chain(),
+    );
 }
@@ -1,49 +1,86 @@
 use bevy::prelude::*;
-use bevy_mod_index::index::Index;
 
 use crate::{
-    easings::{bell_curve, CombinedEasing},
-    grid::position::GridPosition,
-    materials::{rect_outlined::OutlinedRectMaterial, ATTRIBUTE_RECT_SIZE},
-    pixels::{
-        components::{AllPixels, Pixel, PixelColor, PixelLifetime, PixelMarker},
-        systems::set_user_pixel,
-        PACKED_SIZE, PIXEL_SIZE, SCANLINE_X, SCANLINE_Y, STARTING_USER_PIXEL,
-    },
+    easings::{asset::EasingAsset, CombinedEasing},
+    grid::{config::GridConfig, mask::GridMask, position::DistanceMetric},
+    pixels::{components::UserPixelMarker, decay::PixelDecay, systems::spawn_pixel_grid},
 };
 
-use super::SceneState;
+use super::{
+    clock::game_millis,
+    cursor::{spawn_scan_cursor, CursorSettings, LightCombine},
+    sweep::ScanSweeps,
+};
 
-#[derive(Reflect, Resource)]
-pub struct PixelStates {
-    pub next_lit_pixel: GridPosition,
-    pub next_lit_time: f64,
+/// Settings that can differ between levels of the game scene.
+#[derive(Reflect, Resource, Debug)]
+#[reflect(Resource)]
+pub struct LevelSettings {
+    /// every scanline moving over the grid, each lighting pixels on its own
+    pub cursors: Vec<CursorSettings>,
+    /// how lights from different cursors on the same pixel combine
+    pub light_combine: LightCombine,
+    /// how pixels without their own `PixelDecay` fade after being lit
+    pub decay: PixelDecay,
+    /// How user pixels without their own `PixelDecay` fade, for example with a `floor` so they
+    /// never go fully dark. Defaults to `decay`.
+    pub user_pixel_decay: Option<PixelDecay>,
+    /// Most user pixels there can be at once. Placing another removes the oldest.
+    pub max_user_pixels: usize,
+    /// When set, user pixels slow the scanline down based on their grid distance from the cursor
+    /// rather than their distance along the scan.
+    pub influence: Option<DistanceMetric>,
+    /// Whether user pixel bell curves wrap around the end of the scan. Every scan starts over
+    /// once it reaches the end, so this is on by default.
+    pub periodic_easing: bool,
+    /// Asset path of a `.mask.txt` layout (like `masks/ring.mask.txt`) or an image giving the
+    /// shape of the grid. The grid is resized to match the mask once it loads.
+    pub mask: Option<String>,
 }
 
-impl Default for PixelStates {
+impl Default for LevelSettings {
     fn default() -> Self {
         Self {
-            next_lit_pixel: GridPosition::new(SCANLINE_X, SCANLINE_Y, 0, 0),
-            next_lit_time: Default::default(),
+            cursors: vec![CursorSettings::default()],
+            light_combine: LightCombine::default(),
+            decay: PixelDecay::default(),
+            user_pixel_decay: None,
+            max_user_pixels: 8,
+            influence: None,
+            periodic_easing: true,
+            mask: None,
         }
     }
 }
 
-impl PixelStates {
-    #[inline]
-    pub fn update_next_pixel(&mut self) {
-        let next_pos = (self.next_lit_pixel.packed + 1) % PACKED_SIZE;
-
-        dbg!(&PACKED_SIZE);
-
-        self.next_lit_pixel.replace(next_pos);
+impl LevelSettings {
+    /// How a pixel fades: its own `decay` if it has one, otherwise the level's decay for its kind.
+    pub fn decay_for<'a>(
+        &'a self,
+        decay: Option<&'a PixelDecay>,
+        user_pixel: bool,
+    ) -> &'a PixelDecay {
+        decay
+            .or(self.user_pixel_decay.as_ref().filter(|_| user_pixel))
+            .unwrap_or(&self.decay)
     }
 }
 
-pub type BellEasingArgs = (f64, f64, f64);
-pub type BellEasingRet = f64;
+/// The layout the current pixel entities were spawned with.
+#[derive(Reflect, Resource)]
+pub struct PixelStates {
+    pub grid: GridConfig,
+    pub mask: Option<GridMask>,
+}
+
+pub const SCAN_EASING_PATH: &str = "easings/scan.easing.ron";
+
+/// The easing asset a cursor's user pixel bell curves are combined with.
+#[derive(Component)]
+pub struct ScanEasing(pub Handle<EasingAsset>);
 
-pub type CombinedBellEasing = CombinedEasing<BellEasingArgs, BellEasingRet>;
+/// Eases a cursor's time between pixels, evaluated on the normalised progress through its scan.
+pub type CombinedBellEasing = CombinedEasing;
 
 // TODO: use required component for scene entities?
 // #[derive(Component)]
@@ -51,49 +88,116 @@ pub type CombinedBellEasing = CombinedEasing<BellEasingArgs, BellEasingRet>;
 
 pub(super) fn setup_game_scene(
     mut commands: Commands,
-    meshes: ResMut<Assets<Mesh>>,
-    outline_mats: ResMut<Assets<OutlinedRectMaterial>>,
-    index: Index<AllPixels>,
+    time: Res<Time<Virtual>>,
+    config: Res<GridConfig>,
+    mask: Option<Res<GridMask>>,
+    level: Res<LevelSettings>,
+    asset_server: Res<AssetServer>,
 ) {
-    commands.init_resource::<PixelStates>();
-    commands.insert_resource(CombinedBellEasing::new(|(x, ..)| x));
+    let state = PixelStates {
+        grid: *config,
+        mask: mask.map(|mask| mask.clone()),
+    };
+
+    let now = game_millis(&time);
+
+    for (index, settings) in level.cursors.iter().enumerate() {
+        spawn_scan_cursor(&mut commands, &asset_server, index, settings, &state, now);
+    }
+
+    spawn_pixel_grid(
+        &mut commands,
+        &config,
+        state.mask.as_ref(),
+        &[(config.center(), UserPixelMarker::default())],
+    );
+
+    commands.insert_resource(state);
+    commands.insert_resource(ScanSweeps::default());
+}
 
-    setup_pixel_grid(&mut commands, meshes, outline_mats);
+/// Where the mask named by `LevelSettings::mask` is loaded from.
+pub enum LevelMaskSource {
+    Layout(Handle<GridMask>),
+    Image(Handle<Image>),
+}
 
-    set_user_pixel(&mut commands, index, STARTING_USER_PIXEL);
+#[derive(Resource)]
+pub struct LevelMask {
+    pub path: String,
+    pub source: LevelMaskSource,
 }
 
-fn setup_pixel_grid(
-    commands: &mut Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<OutlinedRectMaterial>>,
+/// Starts loading the level's mask, or drops the current one, whenever `LevelSettings::mask`
+/// changes.
+pub(super) fn load_level_mask(
+    mut commands: Commands,
+    level: Res<LevelSettings>,
+    current: Option<Res<LevelMask>>,
+    asset_server: Res<AssetServer>,
 ) {
-    for x in 0..SCANLINE_X {
-        for y in 0..SCANLINE_Y {
-            let pixel_mesh = Mesh::from(Rectangle::new(PIXEL_SIZE, PIXEL_SIZE))
-                .with_inserted_attribute(ATTRIBUTE_RECT_SIZE, vec![[PIXEL_SIZE, PIXEL_SIZE]; 4]);
-
-            let outlined_mat = OutlinedRectMaterial {
-                rect_color: LinearRgba {
-                    red: (x as f32 / SCANLINE_X as f32),
-                    green: 1.0,
-                    blue: (y as f32 / SCANLINE_Y as f32),
-                    alpha: 1.0,
-                },
-                outline_color: LinearRgba::WHITE,
-                outline_thickness: 0.0,
-            };
-
-            commands.spawn((
-                StateScoped(SceneState::Game),
-                Pixel {
-                    pos: GridPosition::new(SCANLINE_X, SCANLINE_Y, x, y),
-                },
-                PixelColor(0),
-                PixelLifetime(0.0),
-                MeshMaterial2d(materials.add(outlined_mat)),
-                Mesh2d(meshes.add(pixel_mesh)),
-            ));
+    let Some(path) = &level.mask else {
+        if current.is_some() {
+            commands.remove_resource::<LevelMask>();
+            commands.remove_resource::<GridMask>();
         }
+
+        return;
+    };
+
+    if current.is_some_and(|current| current.path == *path) {
+        return;
+    }
+
+    let source = if path.ends_with(".mask.txt") {
+        LevelMaskSource::Layout(asset_server.load(path))
+    } else {
+        LevelMaskSource::Image(asset_server.load(path))
+    };
+
+    commands.insert_resource(LevelMask {
+        path: path.clone(),
+        source,
+    });
+}
+
+/// Makes the level's mask the active `GridMask` once it has loaded (or reloaded), resizing the
+/// grid to fit it.
+pub(super) fn apply_level_mask(
+    mut commands: Commands,
+    level_mask: Res<LevelMask>,
+    mut layout_events: EventReader<AssetEvent<GridMask>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    layouts: Res<Assets<GridMask>>,
+    images: Res<Assets<Image>>,
+    mut config: ResMut<GridConfig>,
+) {
+    let loaded = match &level_mask.source {
+        LevelMaskSource::Layout(handle) => layout_events
+            .read()
+            .any(|event| event.is_loaded_with_dependencies(handle) || event.is_modified(handle)),
+        LevelMaskSource::Image(handle) => image_events
+            .read()
+            .any(|event| event.is_loaded_with_dependencies(handle) || event.is_modified(handle)),
+    };
+
+    if !loaded && !level_mask.is_changed() {
+        return;
+    }
+
+    let mask = match &level_mask.source {
+        LevelMaskSource::Layout(handle) => layouts.get(handle).cloned(),
+        LevelMaskSource::Image(handle) => images.get(handle).map(GridMask::from_image),
+    };
+
+    let Some(mask) = mask else {
+        return;
+    };
+
+    if config.width != mask.width() || config.height != mask.height() {
+        config.width = mask.width();
+        config.height = mask.height();
     }
+
+    commands.insert_resource(mask);
 }
@@ -0,0 +1,484 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::grid::position::GridPosition;
+
+/// Sent when a cursor lights the first pixel of its scan order.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SweepStarted {
+    /// `ScanCursor::index` of the cursor
+    pub cursor: usize,
+    /// how many sweeps the cursor had completed before this one
+    pub sweep: u64,
+    /// game clock milliseconds the sweep started at
+    pub time: f64,
+}
+
+/// Sent when a cursor has lit the last pixel of its scan order.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SweepCompleted {
+    pub cursor: usize,
+    pub sweep: u64,
+    /// milliseconds from the sweep starting to the next one starting
+    pub duration: f64,
+}
+
+/// Sent when a cursor reaches a pixel on a different row to the last one it reached. Patterns
+/// that don't go row by row send this much more often.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RowStarted {
+    pub cursor: usize,
+    pub row: i32,
+}
+
+/// Sent when a cursor reaches a user pixel.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CursorPassedUserPixel {
+    pub cursor: usize,
+    /// the user pixel
+    pub entity: Entity,
+    /// milliseconds into the sweep the cursor reached it
+    pub offset_ms: f64,
+}
+
+/// Sweep count and timing of one cursor.
+#[derive(Reflect, Default, Debug, Clone, Copy)]
+pub struct SweepTiming {
+    /// sweeps completed so far
+    pub sweeps: u64,
+    /// game clock milliseconds the current sweep started at
+    pub started_at: Option<f64>,
+    /// whether the current sweep was followed from its first pixel, rather than the cursor
+    /// joining it part way through
+    pub from_start: bool,
+    pub last_duration: Option<f64>,
+    /// row of the pixel the cursor last reached
+    pub row: Option<i32>,
+}
+
+/// Sweep timing of every cursor, indexed by `ScanCursor::index`. Reset whenever the game scene
+/// starts.
+#[derive(Reflect, Resource, Default, Debug, Deref)]
+#[reflect(Resource)]
+pub struct ScanSweeps(Vec<SweepTiming>);
+
+impl ScanSweeps {
+    pub fn get(&self, cursor: usize) -> Option<&SweepTiming> {
+        self.0.get(cursor)
+    }
+
+    fn get_mut(&mut self, cursor: usize) -> &mut SweepTiming {
+        if self.0.len() <= cursor {
+            self.0.resize(cursor + 1, SweepTiming::default());
+        }
+
+        &mut self.0[cursor]
+    }
+}
+
+/// Keeps [`ScanSweeps`] up to date and sends the sweep events as the scan steps along.
+#[derive(SystemParam)]
+pub struct SweepTracker<'w> {
+    sweeps: ResMut<'w, ScanSweeps>,
+    started: EventWriter<'w, SweepStarted>,
+    completed: EventWriter<'w, SweepCompleted>,
+    rows: EventWriter<'w, RowStarted>,
+    user_pixels: EventWriter<'w, CursorPassedUserPixel>,
+}
+
+impl SweepTracker<'_> {
+    /// The cursor has reached `pos`, `step` pixels into its scan order, at `time`.
+    pub fn step(&mut self, cursor: usize, step: usize, pos: &GridPosition, time: f64) {
+        let timing = self.sweeps.get_mut(cursor);
+
+        if step == 0 {
+            timing.started_at = Some(time);
+            timing.from_start = true;
+
+            self.started.send(SweepStarted {
+                cursor,
+                sweep: timing.sweeps,
+                time,
+            });
+        } else if timing.started_at.is_none() {
+            timing.started_at = Some(time);
+        }
+
+        let row = pos.y();
+
+        if timing.row != Some(row) {
+            timing.row = Some(row);
+
+            self.rows.send(RowStarted { cursor, row });
+        }
+    }
+
+    /// The pixel the cursor just reached is the user pixel `entity`.
+    pub fn passed_user_pixel(&mut self, cursor: usize, entity: Entity, time: f64) {
+        let timing = self.sweeps.get_mut(cursor);
+
+        self.user_pixels.send(CursorPassedUserPixel {
+            cursor,
+            entity,
+            offset_ms: time - timing.started_at.unwrap_or(time),
+        });
+    }
+
+    /// The cursor has wrapped around, and starts its next sweep at `next_start`.
+    pub fn wrapped(&mut self, cursor: usize, next_start: f64) {
+        let timing = self.sweeps.get_mut(cursor);
+
+        let Some(started_at) = timing.started_at.take() else {
+            return;
+        };
+
+        // a sweep the cursor joined part way through doesn't count
+        if !std::mem::take(&mut timing.from_start) {
+            return;
+        }
+
+        let duration = next_start - started_at;
+
+        timing.last_duration = Some(duration);
+
+        self.completed.send(SweepCompleted {
+            cursor,
+            sweep: timing.sweeps,
+            duration,
+        });
+
+        timing.sweeps += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::world::CommandQueue;
+
+    use crate::{
+        grid::{config::GridConfig, field::InfluenceField, mask::GridMask},
+        pixels::{
+            components::UserPixelMarker,
+            index::PixelGrid,
+            state::PixelLit,
+            systems::{spawn_pixel_grid, update_pixel_lit_time},
+        },
+        scenes::{
+            clock::game_millis,
+            cursor::{CursorSettings, ScanCursor},
+            story::CombinedBellEasing,
+        },
+    };
+
+    use super::*;
+
+    /// A sweep event and what it says about the sweep, for one cursor.
+    #[derive(Debug, PartialEq, Clone, Copy)]
+    enum Sweep {
+        Started { sweep: u64, time: f64 },
+        Row(i32),
+        UserPixel { offset_ms: f64 },
+        Completed { sweep: u64, duration: f64 },
+    }
+
+    #[derive(Resource, Default)]
+    struct Recorded(Vec<(usize, Sweep)>);
+
+    /// Records each frame's events in the order a single step sends them, which is the order
+    /// they were sent in as long as every cursor takes at most one step a frame.
+    fn record_sweeps(
+        mut started: EventReader<SweepStarted>,
+        mut rows: EventReader<RowStarted>,
+        mut user_pixels: EventReader<CursorPassedUserPixel>,
+        mut completed: EventReader<SweepCompleted>,
+        mut recorded: ResMut<Recorded>,
+    ) {
+        let recorded = &mut recorded.0;
+
+        recorded.extend(started.read().map(|event| {
+            let (sweep, time) = (event.sweep, event.time);
+
+            (event.cursor, Sweep::Started { sweep, time })
+        }));
+        recorded.extend(
+            rows.read()
+                .map(|event| (event.cursor, Sweep::Row(event.row))),
+        );
+        recorded.extend(user_pixels.read().map(|event| {
+            let offset_ms = event.offset_ms;
+
+            (event.cursor, Sweep::UserPixel { offset_ms })
+        }));
+        recorded.extend(completed.read().map(|event| {
+            let (sweep, duration) = (event.sweep, event.duration);
+
+            (event.cursor, Sweep::Completed { sweep, duration })
+        }));
+    }
+
+    /// An app scanning `grid`, with nothing scanning it yet.
+    fn scan_app(grid: GridConfig, mask: Option<&GridMask>, user_pixel: Option<(i32, i32)>) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time<Virtual>>();
+        app.init_resource::<PixelGrid>();
+        app.init_resource::<ScanSweeps>();
+        app.init_resource::<Recorded>();
+        app.add_event::<PixelLit>();
+        app.add_event::<SweepStarted>();
+        app.add_event::<SweepCompleted>();
+        app.add_event::<RowStarted>();
+        app.add_event::<CursorPassedUserPixel>();
+        app.add_systems(Update, (update_pixel_lit_time, record_sweeps).chain());
+
+        let world = app.world_mut();
+
+        let user_pixels = user_pixel
+            .map(|(x, y)| (grid.position(x, y), UserPixelMarker::default()))
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let mut queue = CommandQueue::default();
+        spawn_pixel_grid(
+            &mut Commands::new(&mut queue, world),
+            &grid,
+            mask,
+            &user_pixels,
+        );
+        queue.apply(world);
+
+        app
+    }
+
+    /// Adds a cursor that waits 50ms on every pixel, lighting the pixel `skip` steps into its
+    /// scan now.
+    fn add_cursor(
+        app: &mut App,
+        index: usize,
+        grid: &GridConfig,
+        mask: Option<&GridMask>,
+        skip: usize,
+    ) {
+        let world = app.world_mut();
+        let now = game_millis(world.resource::<Time<Virtual>>());
+
+        let mut cursor = ScanCursor::new(index, &CursorSettings::default(), grid, mask, now);
+
+        for _ in 0..skip {
+            cursor.update_next_pixel();
+        }
+
+        world.spawn((
+            cursor,
+            CombinedBellEasing::new(1.0),
+            InfluenceField::default(),
+        ));
+    }
+
+    fn run_frames(app: &mut App, frames: usize) {
+        for _ in 0..frames {
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(10));
+            app.update();
+        }
+    }
+
+    fn recorded(app: &App, cursor: usize) -> Vec<Sweep> {
+        app.world()
+            .resource::<Recorded>()
+            .0
+            .iter()
+            .filter(|(index, _)| *index == cursor)
+            .map(|(_, sweep)| *sweep)
+            .collect()
+    }
+
+    const GRID: GridConfig = GridConfig {
+        width: 3,
+        height: 2,
+        pixel_size: 56.0,
+        gap: 5.0,
+    };
+
+    #[test]
+    fn events_follow_the_scan() {
+        let mut app = scan_app(GRID, None, Some((1, 1)));
+        add_cursor(&mut app, 0, &GRID, None, 0);
+
+        // two full sweeps of six pixels, 300ms each, and the start of a third
+        run_frames(&mut app, 61);
+
+        assert_eq!(
+            recorded(&app, 0),
+            [
+                Sweep::Started {
+                    sweep: 0,
+                    time: 0.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(1),
+                Sweep::UserPixel { offset_ms: 200.0 },
+                Sweep::Completed {
+                    sweep: 0,
+                    duration: 300.0
+                },
+                Sweep::Started {
+                    sweep: 1,
+                    time: 300.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(1),
+                Sweep::UserPixel { offset_ms: 200.0 },
+                Sweep::Completed {
+                    sweep: 1,
+                    duration: 300.0
+                },
+                Sweep::Started {
+                    sweep: 2,
+                    time: 600.0
+                },
+                Sweep::Row(0),
+            ]
+        );
+
+        let timing = app
+            .world()
+            .resource::<ScanSweeps>()
+            .get(0)
+            .copied()
+            .unwrap();
+
+        assert_eq!(timing.sweeps, 2);
+        assert_eq!(timing.last_duration, Some(300.0));
+    }
+
+    #[test]
+    fn masked_rows_are_skipped() {
+        let grid = GridConfig { height: 3, ..GRID };
+
+        let mut mask = GridMask::full(3, 3);
+        for x in 0..3 {
+            mask.set(IVec2::new(x, 1), false);
+        }
+
+        let mut app = scan_app(grid, Some(&mask), Some((2, 2)));
+        add_cursor(&mut app, 0, &grid, Some(&mask), 0);
+
+        run_frames(&mut app, 31);
+
+        // the six pixels left are still 50ms apart, with no row 1 between rows 0 and 2
+        assert_eq!(
+            recorded(&app, 0),
+            [
+                Sweep::Started {
+                    sweep: 0,
+                    time: 0.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(2),
+                Sweep::UserPixel { offset_ms: 250.0 },
+                Sweep::Completed {
+                    sweep: 0,
+                    duration: 300.0
+                },
+                Sweep::Started {
+                    sweep: 1,
+                    time: 300.0
+                },
+                Sweep::Row(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn cursors_added_part_way_through_a_sweep() {
+        let mut app = scan_app(GRID, None, None);
+        add_cursor(&mut app, 0, &GRID, None, 0);
+
+        run_frames(&mut app, 15);
+
+        // one starting its own scan from the beginning, and one joining its scan on the second
+        // row, both half way through the first cursor's sweep
+        add_cursor(&mut app, 1, &GRID, None, 0);
+        add_cursor(&mut app, 2, &GRID, None, 3);
+
+        run_frames(&mut app, 50);
+
+        assert_eq!(
+            recorded(&app, 0),
+            [
+                Sweep::Started {
+                    sweep: 0,
+                    time: 0.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(1),
+                Sweep::Completed {
+                    sweep: 0,
+                    duration: 300.0
+                },
+                Sweep::Started {
+                    sweep: 1,
+                    time: 300.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(1),
+                Sweep::Completed {
+                    sweep: 1,
+                    duration: 300.0
+                },
+                Sweep::Started {
+                    sweep: 2,
+                    time: 600.0
+                },
+                Sweep::Row(0),
+            ]
+        );
+
+        assert_eq!(
+            recorded(&app, 1),
+            [
+                Sweep::Started {
+                    sweep: 0,
+                    time: 150.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(1),
+                Sweep::Completed {
+                    sweep: 0,
+                    duration: 300.0
+                },
+                Sweep::Started {
+                    sweep: 1,
+                    time: 450.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(1),
+            ]
+        );
+
+        // the half sweep it joined isn't counted
+        assert_eq!(
+            recorded(&app, 2),
+            [
+                Sweep::Row(1),
+                Sweep::Started {
+                    sweep: 0,
+                    time: 300.0
+                },
+                Sweep::Row(0),
+                Sweep::Row(1),
+                Sweep::Completed {
+                    sweep: 0,
+                    duration: 300.0
+                },
+                Sweep::Started {
+                    sweep: 1,
+                    time: 600.0
+                },
+                Sweep::Row(0),
+            ]
+        );
+    }
+}
@@ -1,8 +1,25 @@
-use crate::grid::position::GridPosition;
+use rand::seq::IteratorRandom;
 
-pub fn random_grid_position(width: i32, height: i32) -> GridPosition {
-    let random_x = rand::random_range(0..width);
-    let random_y = rand::random_range(0..height);
+use crate::grid::{mask::GridMask, position::GridPosition};
 
-    GridPosition::new(width, height, random_x, random_y)
+/// A random cell of the grid, or `None` if the grid is empty or `mask` leaves no cells to pick
+/// from.
+pub fn random_grid_position(
+    width: i32,
+    height: i32,
+    mask: Option<&GridMask>,
+) -> Option<GridPosition> {
+    if width < 1 || height < 1 {
+        return None;
+    }
+
+    match mask {
+        Some(mask) => mask.cells(width, height).choose(&mut rand::rng()),
+        None => {
+            let random_x = rand::random_range(0..width);
+            let random_y = rand::random_range(0..height);
+
+            Some(GridPosition::new(width, height, random_x, random_y))
+        }
+    }
 }